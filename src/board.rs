@@ -0,0 +1,183 @@
+use std::ops::{Index, IndexMut};
+
+use crate::spawn::SpawnTable;
+use crate::tile::Tile;
+
+// --- Board presets ---
+// '#' is a playable cell, '.' a hole. Rows run top to bottom. Level files
+// pick one by name with `shape <name>`.
+
+pub const SHAPE_PRESETS: [&str; 3] = ["heart", "ring", "split"];
+
+pub const SHAPE_HEART: [&str; 8] = [
+    ".##..##.",
+    "########",
+    "########",
+    "########",
+    ".######.",
+    "..####..",
+    "..####..",
+    "...##...",
+];
+
+pub const SHAPE_RING: [&str; 8] = [
+    "..####..",
+    ".######.",
+    "###..###",
+    "##....##",
+    "##....##",
+    "###..###",
+    ".######.",
+    "..####..",
+];
+
+pub const SHAPE_SPLIT: [&str; 8] = [
+    "###..###",
+    "###..###",
+    "###..###",
+    "########",
+    "########",
+    "###..###",
+    "###..###",
+    "###..###",
+];
+
+/// Dimensions plus which cells are playable. `mask[x][y]` is false for holes.
+#[derive(Clone, PartialEq, Debug)]
+pub struct BoardShape {
+    pub width: usize,
+    pub height: usize,
+    pub mask: Vec<Vec<bool>>,
+}
+
+impl BoardShape {
+    pub fn rect(width: usize, height: usize) -> Self {
+        Self { width, height, mask: vec![vec![true; height]; width] }
+    }
+
+    /// Parse rows of '#' (cell) and '.' (hole). All rows must be the same width.
+    pub fn from_rows<S: AsRef<str>>(rows: &[S]) -> Option<Self> {
+        let height = rows.len();
+        let width = rows.first()?.as_ref().chars().count();
+        if width == 0 {
+            return None;
+        }
+        let mut mask = vec![vec![false; height]; width];
+        for (y, row) in rows.iter().enumerate() {
+            let row = row.as_ref();
+            if row.chars().count() != width {
+                return None;
+            }
+            for (x, c) in row.chars().enumerate() {
+                mask[x][y] = match c {
+                    '#' => true,
+                    '.' => false,
+                    _ => return None,
+                };
+            }
+        }
+        Some(Self { width, height, mask })
+    }
+
+    pub fn preset(name: &str) -> Option<Self> {
+        match name {
+            "heart" => Self::from_rows(&SHAPE_HEART),
+            "ring" => Self::from_rows(&SHAPE_RING),
+            "split" => Self::from_rows(&SHAPE_SPLIT),
+            _ => None,
+        }
+    }
+}
+
+/// The puzzle grid, sized at runtime. Index with `board[x][y]` like the old
+/// fixed array; holes hold `Tile::EMPTY` and are never filled.
+#[derive(Clone, Debug)]
+pub struct Board {
+    shape: BoardShape,
+    columns: Vec<Vec<Tile>>,
+    spawn: SpawnTable, // what refills and fresh boards draw from
+}
+
+impl Board {
+    pub fn new(shape: BoardShape) -> Self {
+        let columns = vec![vec![Tile::EMPTY; shape.height]; shape.width];
+        Self { shape, columns, spawn: SpawnTable::default() }
+    }
+
+    pub fn width(&self) -> usize {
+        self.shape.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.shape.height
+    }
+
+    pub fn shape(&self) -> &BoardShape {
+        &self.shape
+    }
+
+    pub fn has_holes(&self) -> bool {
+        self.shape.mask.iter().flatten().any(|cell| !cell)
+    }
+
+    pub fn is_playable(&self, x: usize, y: usize) -> bool {
+        x < self.width() && y < self.height() && self.shape.mask[x][y]
+    }
+
+    /// Every playable cell, column by column.
+    pub fn cells(&self) -> Vec<(usize, usize)> {
+        (0..self.width())
+            .flat_map(|x| (0..self.height()).map(move |y| (x, y)))
+            .filter(|(x, y)| self.shape.mask[*x][*y])
+            .collect()
+    }
+
+    /// Playable rows of column `x`, top to bottom.
+    pub fn column_cells(&self, x: usize) -> Vec<usize> {
+        (0..self.height()).filter(|y| self.shape.mask[x][*y]).collect()
+    }
+
+    pub fn orthogonal_neighbours(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        let mut cells = vec![];
+        if x > 0 {
+            cells.push((x - 1, y));
+        }
+        cells.push((x + 1, y));
+        if y > 0 {
+            cells.push((x, y - 1));
+        }
+        cells.push((x, y + 1));
+        cells.retain(|(nx, ny)| self.is_playable(*nx, *ny));
+        cells
+    }
+
+    pub fn spawn_table(&self) -> &SpawnTable {
+        &self.spawn
+    }
+
+    pub fn spawn_table_mut(&mut self) -> &mut SpawnTable {
+        &mut self.spawn
+    }
+
+    pub fn tiles(&self) -> impl Iterator<Item = &Tile> {
+        self.columns.iter().flatten()
+    }
+
+    pub fn columns_mut(&mut self) -> impl Iterator<Item = &mut Vec<Tile>> {
+        self.columns.iter_mut()
+    }
+}
+
+impl Index<usize> for Board {
+    type Output = [Tile];
+
+    fn index(&self, x: usize) -> &[Tile] {
+        &self.columns[x]
+    }
+}
+
+impl IndexMut<usize> for Board {
+    fn index_mut(&mut self, x: usize) -> &mut [Tile] {
+        &mut self.columns[x]
+    }
+}
@@ -0,0 +1,184 @@
+// --- Board input: drag gestures and the keyboard cursor ---
+//
+// Clicks select tiles one at a time. On top of that a tile can be dragged
+// onto a neighbour, and a cursor can be steered with the arrow keys or WASD
+// and confirmed with Enter or Space. Every path ends in the same `Select`
+// actions as clicking, so replays and the swap rules don't see a difference.
+//
+// macroquad has no gamepad API, so controllers aren't read directly. A pad
+// whose d-pad and face buttons are mapped to arrow keys and Enter (Steam
+// Input, for one) drives the cursor as-is.
+
+use macroquad::prelude::*;
+
+use crate::game_state::GameState;
+use crate::replay::ReplayAction;
+use crate::ui_layout::Layout;
+
+pub const DRAG_SWAP_THRESHOLD: f32 = 0.45; // of a tile; a shorter drag is just a click
+pub const DRAG_NUDGE_MAX: f32 = 0.3;       // how far the held tile follows the pointer, in tiles
+
+/// A tile held under the mouse button.
+#[derive(Clone, Copy, Debug)]
+pub struct BoardDrag {
+    pub cell: (usize, usize),
+    pub start_x: f32,
+    pub start_y: f32,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum CursorCommand {
+    Move(isize, isize),
+    Confirm,
+    Cancel,
+}
+
+fn read_cursor_commands() -> Vec<CursorCommand> {
+    let bindings = [
+        (KeyCode::Left, CursorCommand::Move(-1, 0)),
+        (KeyCode::A, CursorCommand::Move(-1, 0)),
+        (KeyCode::Right, CursorCommand::Move(1, 0)),
+        (KeyCode::D, CursorCommand::Move(1, 0)),
+        (KeyCode::Up, CursorCommand::Move(0, -1)),
+        (KeyCode::W, CursorCommand::Move(0, -1)),
+        (KeyCode::Down, CursorCommand::Move(0, 1)),
+        (KeyCode::S, CursorCommand::Move(0, 1)),
+        (KeyCode::Enter, CursorCommand::Confirm),
+        (KeyCode::Space, CursorCommand::Confirm),
+        (KeyCode::Backspace, CursorCommand::Cancel),
+    ];
+    bindings
+        .iter()
+        .filter(|(key, _)| is_key_pressed(*key))
+        .map(|(_, command)| *command)
+        .collect()
+}
+
+/// The neighbour a drag from `start` by (dx, dy) pixels points at, if it went
+/// far enough. The longer axis wins.
+fn drag_target(start: (usize, usize), dx: f32, dy: f32, tile_size: f32) -> Option<(isize, isize)> {
+    let threshold = tile_size * DRAG_SWAP_THRESHOLD;
+    if dx.abs().max(dy.abs()) < threshold {
+        return None;
+    }
+    let (x, y) = (start.0 as isize, start.1 as isize);
+    Some(if dx.abs() >= dy.abs() {
+        (x + dx.signum() as isize, y)
+    } else {
+        (x, y + dy.signum() as isize)
+    })
+}
+
+impl GameState {
+    /// Mouse presses, drags and cursor keys for an idle board.
+    pub(crate) fn handle_board_input(&mut self, layout: &Layout) {
+        let (mx, my) = mouse_position();
+        if is_mouse_button_pressed(MouseButton::Left) {
+            self.cursor = None;
+            self.handle_playing_click(mx, my, layout);
+            self.start_drag(mx, my, layout);
+        } else if is_mouse_button_pressed(MouseButton::Right) {
+            self.handle_playing_right_click(mx, my, layout);
+        } else if self.drag.is_some() {
+            self.update_drag(mx, my, layout);
+        }
+        self.handle_cursor_keys();
+    }
+
+    /// Start holding the tile under the pointer if the press just selected it.
+    fn start_drag(&mut self, mx: f32, my: f32, layout: &Layout) {
+        let gx = ((mx - layout.grid_offset_x) / layout.tile_size).floor();
+        let gy = ((my - layout.grid_offset_y) / layout.tile_size).floor();
+        if gx < 0.0 || gy < 0.0 {
+            return;
+        }
+        let cell = (gx as usize, gy as usize);
+        if self.selected == Some(cell) {
+            self.drag = Some(BoardDrag { cell, start_x: mx, start_y: my });
+        }
+    }
+
+    /// Nudge the held tile after the pointer, and swap on release over a neighbour.
+    fn update_drag(&mut self, mx: f32, my: f32, layout: &Layout) {
+        let Some(drag) = self.drag else {
+            return;
+        };
+        let (dx, dy) = (mx - drag.start_x, my - drag.start_y);
+        let (x, y) = drag.cell;
+        if is_mouse_button_down(MouseButton::Left) {
+            let reach = layout.tile_size * DRAG_NUDGE_MAX;
+            let tile = &mut self.grid[x][y];
+            tile.fall_velocity = 0.0;
+            if dx.abs() >= dy.abs() {
+                tile.offset_x = dx.clamp(-reach, reach);
+                tile.offset_y = 0.0;
+            } else {
+                tile.offset_x = 0.0;
+                tile.offset_y = dy.clamp(-reach, reach);
+            }
+            return;
+        }
+
+        self.drag = None;
+        let Some((tx, ty)) = drag_target(drag.cell, dx, dy, layout.tile_size) else {
+            return;
+        };
+        if tx >= 0 && ty >= 0 && self.grid.is_playable(tx as usize, ty as usize) && self.selected == Some(drag.cell) {
+            self.reset_idle_hint();
+            self.perform_action(ReplayAction::Select { x: tx as usize, y: ty as usize });
+        }
+    }
+
+    /// Steer the cursor over playable cells. The first key press only shows it.
+    fn handle_cursor_keys(&mut self) {
+        for command in read_cursor_commands() {
+            self.reset_idle_hint();
+            let Some((cx, cy)) = self.cursor else {
+                self.cursor = self.selected.or_else(|| self.cursor_home());
+                continue;
+            };
+            match command {
+                CursorCommand::Move(dx, dy) => self.cursor = Some(self.cursor_step((cx, cy), dx, dy)),
+                CursorCommand::Confirm => self.perform_action(ReplayAction::Select { x: cx, y: cy }),
+                CursorCommand::Cancel if self.armed.is_some() => self.perform_action(ReplayAction::Disarm),
+                CursorCommand::Cancel if self.selected.is_some() => self.perform_action(ReplayAction::Deselect),
+                CursorCommand::Cancel => {}
+            }
+        }
+    }
+
+    /// The board cell under the mouse, if any.
+    pub(crate) fn hovered_cell(&self, layout: &Layout) -> Option<(usize, usize)> {
+        let (mx, my) = mouse_position();
+        let gx = ((mx - layout.grid_offset_x) / layout.tile_size).floor();
+        let gy = ((my - layout.grid_offset_y) / layout.tile_size).floor();
+        let (x, y) = (gx as usize, gy as usize);
+        (gx >= 0.0 && gy >= 0.0 && x < self.grid.width() && y < self.grid.height()).then_some((x, y))
+    }
+
+    /// The playable cell nearest the middle of the board.
+    fn cursor_home(&self) -> Option<(usize, usize)> {
+        let mid_x = (self.grid.width() as f32 - 1.0) * 0.5;
+        let mid_y = (self.grid.height() as f32 - 1.0) * 0.5;
+        self.grid.cells().into_iter().min_by(|a, b| {
+            let da = (a.0 as f32 - mid_x).abs() + (a.1 as f32 - mid_y).abs();
+            let db = (b.0 as f32 - mid_x).abs() + (b.1 as f32 - mid_y).abs();
+            da.total_cmp(&db)
+        })
+    }
+
+    /// One step in a direction, hopping over holes. Stays put at the edge.
+    fn cursor_step(&self, from: (usize, usize), dx: isize, dy: isize) -> (usize, usize) {
+        let (mut x, mut y) = (from.0 as isize, from.1 as isize);
+        loop {
+            x += dx;
+            y += dy;
+            if x < 0 || y < 0 || x >= self.grid.width() as isize || y >= self.grid.height() as isize {
+                return from;
+            }
+            if self.grid.is_playable(x as usize, y as usize) {
+                return (x as usize, y as usize);
+            }
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use crate::board::Board;
+use crate::match_logic;
+use crate::rng::RngStream;
+use crate::tile::{SpecialGem, Tile, TileType};
+
+/// Give up reshuffling the same tiles after this many tries and reroll instead.
+pub const SHUFFLE_MAX_ATTEMPTS: usize = 200;
+/// Give up rerolling after this many fresh sets of tiles.
+pub const REROLL_MAX_ATTEMPTS: usize = 20;
+
+/// One adjacent swap the player could make.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SwapMove {
+    pub from: (usize, usize),
+    pub to: (usize, usize),
+}
+
+/// True if swapping the two cells would clear something: a match group or a
+/// color-clear gem swapped into any tile.
+pub fn swap_makes_match(grid: &Board, mv: SwapMove) -> bool {
+    let (a, b) = (grid[mv.from.0][mv.from.1], grid[mv.to.0][mv.to.1]);
+    if a.kind == TileType::Empty || b.kind == TileType::Empty || a.blocker || b.blocker {
+        return false;
+    }
+    if a.special == Some(SpecialGem::ColorClear) || b.special == Some(SpecialGem::ColorClear) {
+        return a.kind.is_gem() && b.kind.is_gem();
+    }
+
+    let mut trial = grid.clone();
+    trial[mv.from.0][mv.from.1] = b;
+    trial[mv.to.0][mv.to.1] = a;
+    !match_logic::find_matches(&trial).is_empty()
+}
+
+/// Every right/down neighbour swap that produces a match.
+pub fn find_valid_moves(grid: &Board) -> Vec<SwapMove> {
+    let mut moves = vec![];
+    for (x, y) in grid.cells() {
+        for to in [(x + 1, y), (x, y + 1)] {
+            if grid.is_playable(to.0, to.1) {
+                let mv = SwapMove { from: (x, y), to };
+                if swap_makes_match(grid, mv) {
+                    moves.push(mv);
+                }
+            }
+        }
+    }
+    moves
+}
+
+pub fn has_valid_move(grid: &Board) -> bool {
+    !find_valid_moves(grid).is_empty()
+}
+
+/// Fisher-Yates over every non-empty tile, keeping the same tiles (and any
+/// special gems) but in new cells. Blocked tiles and ingredients stay where
+/// they are, so an ingredient never lands in the bottom row undelivered.
+pub fn shuffle_tiles(grid: &mut Board, rng: &mut RngStream) {
+    let cells: Vec<(usize, usize)> = grid
+        .cells()
+        .into_iter()
+        .filter(|(x, y)| {
+            let tile = grid[*x][*y];
+            tile.kind != TileType::Empty && tile.kind != TileType::Ingredient && !tile.blocker
+        })
+        .collect();
+    for i in (1..cells.len()).rev() {
+        let j = rng.gen_range(0, i + 1);
+        let (ax, ay) = cells[i];
+        let (bx, by) = cells[j];
+        let temp = grid[ax][ay];
+        grid[ax][ay] = grid[bx][by];
+        grid[bx][by] = temp;
+    }
+    for column in grid.columns_mut() {
+        for tile in column.iter_mut() {
+            tile.offset_x = 0.0;
+            tile.offset_y = 0.0;
+            tile.fall_velocity = 0.0;
+            tile.fall_delay = 0.0;
+        }
+    }
+}
+
+/// Let surviving tiles drop into cleared cells without refilling, so the
+/// hint heuristic can see which cascades a move would set up.
+fn collapse_without_refill(grid: &mut Board) {
+    for x in 0..grid.width() {
+        let rows = grid.column_cells(x);
+        let survivors: Vec<Tile> = rows.iter().map(|y| grid[x][*y]).filter(|tile| tile.kind != TileType::Empty).collect();
+        let gap = rows.len() - survivors.len();
+        for (index, y) in rows.into_iter().enumerate() {
+            grid[x][y] = if index < gap { Tile::EMPTY } else { survivors[index - gap] };
+        }
+    }
+}
+
+/// Heuristic value of a move: tiles cleared, longer and crossing groups (which
+/// make special gems), and whatever the collapse would match next.
+pub fn score_move(grid: &Board, mv: SwapMove) -> i32 {
+    let (a, b) = (grid[mv.from.0][mv.from.1], grid[mv.to.0][mv.to.1]);
+    if a.special == Some(SpecialGem::ColorClear) || b.special == Some(SpecialGem::ColorClear) {
+        let target = if a.special == Some(SpecialGem::ColorClear) { b.kind } else { a.kind };
+        let hits = grid.tiles().filter(|tile| tile.kind == target).count();
+        return hits as i32 * match_logic::MATCH_POINTS_PER_TILE;
+    }
+
+    let mut trial = grid.clone();
+    trial[mv.from.0][mv.from.1] = b;
+    trial[mv.to.0][mv.to.1] = a;
+    let groups = match_logic::find_matches(&trial);
+    if groups.is_empty() {
+        return 0;
+    }
+
+    let mut score = 0;
+    for group in &groups {
+        score += group.cells.len() as i32 * match_logic::MATCH_POINTS_PER_TILE;
+        score += group.length.saturating_sub(3) as i32 * 20;
+        if group.intersection.is_some() {
+            score += 30;
+        }
+    }
+
+    for (x, y) in groups.iter().flat_map(|group| group.cells.iter()) {
+        trial[*x][*y] = Tile::EMPTY;
+    }
+    collapse_without_refill(&mut trial);
+    let cascade_cells: usize = match_logic::find_matches(&trial).iter().map(|group| group.cells.len()).sum();
+    score + cascade_cells as i32 * match_logic::MATCH_POINTS_PER_TILE / 2
+}
+
+/// The move the idle hint should point at.
+pub fn best_hint_move(grid: &Board) -> Option<SwapMove> {
+    find_valid_moves(grid)
+        .into_iter()
+        .max_by_key(|mv| score_move(grid, *mv))
+}
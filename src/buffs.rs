@@ -0,0 +1,172 @@
+// --- Food buffs ---
+//
+// Tarquin's food, eaten from the inventory with keys 5 to 7, on the board or
+// at the shrine. Mint Leaf pays out on the next clear. Honey Bread lasts one
+// level: the one being played, or the next one when eaten at the shrine
+// between biomes. Spiced Nut always waits for the next level.
+//
+// Spiced Nut comes off each illicit charge rather than the starting cost in
+// `reset_illegal_move_cost`: the cost only resets between biomes, so a lower
+// start would do nothing for most levels and would outlast the level it was
+// eaten for.
+
+use macroquad::prelude::*;
+
+use crate::economy;
+use crate::game_state::{GamePhase, GameState};
+use crate::inventory::{FoodBuffKind, ItemType};
+use crate::modifiers::ModifierBadge;
+use crate::replay::ReplayAction;
+
+pub const MINT_LEAF_BONUS: i32 = 60;
+pub const HONEY_DROP_SCALE: f32 = 1.5; // drop chances while Honey Bread lasts
+pub const SPICED_NUT_DISCOUNT: i32 = 50;
+
+/// Key order for eating food, after the four board modifiers: 5 to 7.
+pub const FOOD_KINDS: [FoodBuffKind; 3] = [FoodBuffKind::MintLeaf, FoodBuffKind::HoneyBread, FoodBuffKind::SpicedNut];
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BuffExpiry {
+    NextClear,  // spent by the next clear that lands
+    Level(i32), // runs out when a later level starts
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ActiveBuff {
+    pub kind: FoodBuffKind,
+    pub expiry: BuffExpiry,
+}
+
+/// Short label and colour for a buff's badge or inventory chip.
+pub fn buff_badge_style(kind: FoodBuffKind) -> (&'static str, Color) {
+    match kind {
+        FoodBuffKind::MintLeaf => ("MINT", color_u8!(110, 210, 170, 255)),
+        FoodBuffKind::HoneyBread => ("HONY", color_u8!(230, 180, 70, 255)),
+        FoodBuffKind::SpicedNut => ("NUT", color_u8!(190, 110, 70, 255)),
+    }
+}
+
+impl GameState {
+    /// Keys 5 to 7 eat the matching food. On the board they wait for it to
+    /// settle, so a recording never holds a meal that was refused live.
+    pub(crate) fn handle_food_keys(&mut self) {
+        if self.phase == GamePhase::Playing && !self.board_idle() {
+            return;
+        }
+        let keys = [KeyCode::Key5, KeyCode::Key6, KeyCode::Key7];
+        for (key, kind) in keys.iter().zip(FOOD_KINDS) {
+            if is_key_pressed(*key) {
+                self.perform_action(ReplayAction::EatFood(kind));
+            }
+        }
+    }
+
+    pub(crate) fn has_buff(&self, kind: FoodBuffKind) -> bool {
+        self.buffs.iter().any(|buff| buff.kind == kind)
+    }
+
+    /// Eat one carried food. Returns false if there's none, it's already
+    /// active, or this isn't a moment to eat (a busy board, another screen).
+    pub(crate) fn eat_food(&mut self, kind: FoodBuffKind) -> bool {
+        let item = ItemType::FoodBuff(kind);
+        let level = match self.phase {
+            GamePhase::Shop => self.level + 1,
+            _ if self.board_idle() => self.level,
+            _ => return false,
+        };
+        if self.has_buff(kind) || !self.inventory.take_one(item) {
+            return false;
+        }
+        let expiry = match kind {
+            FoodBuffKind::MintLeaf => BuffExpiry::NextClear,
+            FoodBuffKind::HoneyBread => BuffExpiry::Level(level),
+            FoodBuffKind::SpicedNut => BuffExpiry::Level(self.level + 1),
+        };
+        self.buffs.push(ActiveBuff { kind, expiry });
+        self.drop_undo();
+        true
+    }
+
+    /// Drop level buffs for levels already behind us. Call when a level starts.
+    pub(crate) fn expire_buffs(&mut self) {
+        let level = self.level;
+        self.buffs.retain(|buff| match buff.expiry {
+            BuffExpiry::NextClear => true,
+            BuffExpiry::Level(until) => until >= level,
+        });
+    }
+
+    /// A level buff counts only on its own level; one eaten at the shrine
+    /// waits for the next.
+    fn buff_active(&self, kind: FoodBuffKind) -> bool {
+        self.buffs.iter().any(|buff| {
+            buff.kind == kind
+                && match buff.expiry {
+                    BuffExpiry::NextClear => true,
+                    BuffExpiry::Level(until) => until == self.level,
+                }
+        })
+    }
+
+    /// Spend a Mint Leaf on the clear that just landed, if one is waiting.
+    pub(crate) fn take_mint_bonus(&mut self) -> i32 {
+        let before = self.buffs.len();
+        self.buffs.retain(|buff| buff.kind != FoodBuffKind::MintLeaf);
+        if self.buffs.len() < before {
+            MINT_LEAF_BONUS
+        } else {
+            0
+        }
+    }
+
+    /// How much likelier drops are right now.
+    pub(crate) fn drop_rate_scale(&self) -> f32 {
+        if self.buff_active(FoodBuffKind::HoneyBread) {
+            HONEY_DROP_SCALE
+        } else {
+            1.0
+        }
+    }
+
+    /// What Spiced Nut takes off each illicit move this level, once the
+    /// level it was eaten for has started.
+    pub(crate) fn illicit_discount(&self) -> i32 {
+        if self.buff_active(FoodBuffKind::SpicedNut) {
+            SPICED_NUT_DISCOUNT
+        } else {
+            0
+        }
+    }
+
+    pub(crate) fn buff_badges(&self) -> Vec<ModifierBadge> {
+        self.buffs
+            .iter()
+            .map(|buff| {
+                let (label, color) = buff_badge_style(buff.kind);
+                let effect = match buff.kind {
+                    FoodBuffKind::MintLeaf => format!("+{} next clear", MINT_LEAF_BONUS),
+                    FoodBuffKind::HoneyBread => format!("Drops x{}", HONEY_DROP_SCALE),
+                    FoodBuffKind::SpicedNut => format!("Illicit -{}", SPICED_NUT_DISCOUNT),
+                };
+                let detail = match buff.expiry {
+                    BuffExpiry::Level(until) if until > self.level => format!("{} (next level)", effect),
+                    BuffExpiry::Level(_) => format!("{} (level)", effect),
+                    BuffExpiry::NextClear => effect,
+                };
+                ModifierBadge { label, color, detail }
+            })
+            .collect()
+    }
+
+    /// Carried food with its key, for the HUD and the shrine.
+    pub(crate) fn carried_food_lines(&self) -> Vec<String> {
+        FOOD_KINDS
+            .iter()
+            .enumerate()
+            .filter_map(|(index, kind)| {
+                let count = economy::inventory_count(&self.inventory, ItemType::FoodBuff(*kind));
+                (count > 0).then(|| format!("[{}] {} x{}", index + 5, economy::item_name(ItemType::FoodBuff(*kind)), count))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,116 @@
+// --- Discarding to Bagira ---
+//
+// Right-click an item in the HUD satchel or the garden drawer and Bagira buys
+// one of it at her buyback rate, then keeps it in her reserve to sell back.
+// Items worth DISCARD_CONFIRM_PRICE or more ask first. Only the sale itself
+// is recorded for replays; the dialog is just a question.
+
+use macroquad::prelude::*;
+
+use crate::economy;
+use crate::game_state::GameState;
+use crate::inventory::ItemType;
+use crate::render;
+use crate::replay::ReplayAction;
+use crate::shop;
+
+pub const DISCARD_CONFIRM_PRICE: i32 = 500; // base price from which a sale asks first
+pub const DISCARD_NOTE_LIFE: f32 = 2.5;     // seconds Bagira's reply stays up
+pub const BAGIRA_DISCARD_LINE: &str = "You can't carry that. I'll take it off your hands.";
+
+/// A sale waiting for the player to say yes.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct DiscardConfirm {
+    pub slot: usize,
+    pub item: ItemType,
+    pub paid: i32,
+}
+
+/// Bagira's reply after a sale.
+#[derive(Clone, Debug)]
+pub struct DiscardNote {
+    pub text: String,
+    pub age: f32,
+}
+
+impl DiscardNote {
+    /// Fades over the last half second.
+    pub fn alpha(&self) -> f32 {
+        ((DISCARD_NOTE_LIFE - self.age) / 0.5).clamp(0.0, 1.0)
+    }
+}
+
+impl GameState {
+    /// A right-click on a slot: sell straight away, or ask first for
+    /// something valuable.
+    pub(crate) fn request_discard(&mut self, slot: usize) {
+        let Some(item) = self.inventory.slots.get(slot).and_then(|slot| slot.item) else {
+            return;
+        };
+        if !self.can_edit_inventory() {
+            return;
+        }
+        let base_price = economy::base_price_for_item(item);
+        if base_price >= DISCARD_CONFIRM_PRICE {
+            self.discard_confirm = Some(DiscardConfirm { slot, item, paid: shop::buyback_payout(base_price) });
+        } else {
+            self.perform_action(ReplayAction::Discard { slot });
+        }
+    }
+
+    /// Y or Enter sells, N or Escape keeps the item.
+    pub(crate) fn update_discard_confirm(&mut self) {
+        let Some(confirm) = self.discard_confirm else {
+            return;
+        };
+        if !self.can_edit_inventory() {
+            self.discard_confirm = None;
+            return;
+        }
+        if is_key_pressed(KeyCode::Y) || is_key_pressed(KeyCode::Enter) {
+            self.discard_confirm = None;
+            self.perform_action(ReplayAction::Discard { slot: confirm.slot });
+        } else if is_key_pressed(KeyCode::N) || is_key_pressed(KeyCode::Escape) {
+            self.discard_confirm = None;
+        }
+    }
+
+    pub(crate) fn discard_slot(&mut self, slot: usize) {
+        if slot >= self.inventory.slots.len() || !self.can_edit_inventory() {
+            return;
+        }
+        let Some((item, paid)) =
+            economy::discard_to_bagira(&mut self.inventory, &mut self.shop, &mut self.total_points, slot)
+        else {
+            return;
+        };
+        self.drop_undo();
+        if let Some(armed) = self.armed {
+            if economy::inventory_count(&self.inventory, ItemType::BoardModifier(armed.kind)) == 0 {
+                self.armed = None;
+            }
+        }
+        self.discard_note = Some(DiscardNote {
+            text: format!("Bagira: \"{}\"  {} +{} Leaves", BAGIRA_DISCARD_LINE, economy::item_name(item), paid),
+            age: 0.0,
+        });
+    }
+
+    pub(crate) fn draw_discard_overlays(&self) {
+        if let Some(note) = &self.discard_note {
+            render::draw_discard_note(&note.text, note.alpha());
+        }
+        if let Some(confirm) = self.discard_confirm {
+            render::draw_discard_confirm(economy::item_name(confirm.item), confirm.paid);
+        }
+    }
+
+    pub(crate) fn update_discard_note(&mut self, delta: f32) {
+        if let Some(note) = &mut self.discard_note {
+            note.age += delta;
+            if note.age >= DISCARD_NOTE_LIFE {
+                self.discard_note = None;
+            }
+        }
+    }
+}
@@ -1,6 +1,5 @@
-use macroquad::rand::gen_range;
-
-use crate::inventory::{Inventory, ItemType};
+use crate::inventory::{BoardModifierKind, FoodBuffKind, Inventory, ItemType};
+use crate::rng::RngStream;
 use crate::shop::Shop;
 use crate::tile::TileType;
 
@@ -30,58 +29,116 @@ pub fn base_price_for_item(item: ItemType) -> i32 {
     }
 }
 
+pub fn item_name(item: ItemType) -> &'static str {
+    match item {
+        ItemType::WateringCan => "Watering Can",
+        ItemType::SeedDay => "Day Seed",
+        ItemType::SeedNight => "Night Seed",
+        ItemType::MoonbloomEssence => "Moonbloom Essence",
+        ItemType::Fertilizer => "Fertilizer",
+        ItemType::BoardModifier(BoardModifierKind::TileWeightShift) => "Tile Charm",
+        ItemType::BoardModifier(BoardModifierKind::IllegalCostCap) => "Cost Cap",
+        ItemType::BoardModifier(BoardModifierKind::CascadeBonus) => "Cascade Charm",
+        ItemType::BoardModifier(BoardModifierKind::FogClear) => "Fog Clear",
+        ItemType::BoardModifier(BoardModifierKind::TileRemove) => "Trowel",
+        ItemType::BoardModifier(BoardModifierKind::FreeSwap) => "Swap Twine",
+        ItemType::BoardModifier(BoardModifierKind::RowClear) => "Scythe",
+        ItemType::FoodBuff(FoodBuffKind::MintLeaf) => "Mint Leaf",
+        ItemType::FoodBuff(FoodBuffKind::HoneyBread) => "Honey Bread",
+        ItemType::FoodBuff(FoodBuffKind::SpicedNut) => "Spiced Nut",
+    }
+}
+
+/// What a cleared tile gave the player, so it can be shown where it dropped.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ResourceDrop {
+    Leaves(i32),
+    Item(ItemType),
+    Consigned { item: ItemType, paid: i32 }, // inventory was full; Bagira paid for it
+    Stored(ItemType),                        // inventory was full; went to the shed
+}
+
+/// Overflow goes to the garden shed when the player routes it there and it
+/// has room, otherwise to Bagira.
 pub fn add_resource_or_consign(
     inventory: &mut Inventory,
+    shed: Option<&mut Inventory>,
     shop: &mut Shop,
     total_points: &mut i32,
     item: ItemType,
-) {
-    if !inventory.push(item) {
-        let paid = shop.bagira.consign(item, base_price_for_item(item));
-        *total_points += paid;
+) -> ResourceDrop {
+    if inventory.push(item) {
+        return ResourceDrop::Item(item);
     }
+    if shed.is_some_and(|shed| shed.push(item)) {
+        return ResourceDrop::Stored(item);
+    }
+    let paid = shop.bagira.consign(item, base_price_for_item(item));
+    *total_points += paid;
+    ResourceDrop::Consigned { item, paid }
+}
+
+/// Sell one unit from `slot_index` to Bagira. Returns the item and the
+/// leaves she paid, or None for an empty slot.
+pub fn discard_to_bagira(
+    inventory: &mut Inventory,
+    shop: &mut Shop,
+    total_points: &mut i32,
+    slot_index: usize,
+) -> Option<(ItemType, i32)> {
+    let item = inventory.discard_one(slot_index)?;
+    let paid = shop.bagira.consign(item, base_price_for_item(item));
+    *total_points += paid;
+    Some((item, paid))
 }
 
+/// `rate_scale` multiplies every drop chance (Honey Bread). The rolls drawn
+/// are the same either way, so the drop stream stays in step.
 pub fn roll_resource_drop(
     tile_kind: TileType,
     inventory: &mut Inventory,
+    shed: Option<&mut Inventory>,
     shop: &mut Shop,
     total_points: &mut i32,
-) {
-    let roll = gen_range(0.0, 1.0);
+    rng: &mut RngStream,
+    rate_scale: f32,
+) -> Option<ResourceDrop> {
+    let roll = rng.gen_f32() / rate_scale;
     match tile_kind {
         TileType::Leaf => {
             if roll < DROP_RATE_LEAF_LEAVES {
                 *total_points += LEAF_DROP_BONUS;
+                return Some(ResourceDrop::Leaves(LEAF_DROP_BONUS));
             }
         }
         TileType::Water => {
             if roll < DROP_RATE_WATERING_CAN {
-                add_resource_or_consign(inventory, shop, total_points, ItemType::WateringCan);
+                return Some(add_resource_or_consign(inventory, shed, shop, total_points, ItemType::WateringCan));
             }
         }
         TileType::Sun => {
             if roll < DROP_RATE_SEED_DAY {
-                add_resource_or_consign(inventory, shop, total_points, ItemType::SeedDay);
+                return Some(add_resource_or_consign(inventory, shed, shop, total_points, ItemType::SeedDay));
             }
         }
         TileType::Moon => {
             if roll < DROP_RATE_MOON_ITEM {
-                let moon_item = if gen_range(0.0, 1.0) < 0.30 {
+                let moon_item = if rng.gen_f32() < 0.30 {
                     ItemType::MoonbloomEssence
                 } else {
                     ItemType::SeedNight
                 };
-                add_resource_or_consign(inventory, shop, total_points, moon_item);
+                return Some(add_resource_or_consign(inventory, shed, shop, total_points, moon_item));
             }
         }
         TileType::Exotic => {
             if roll < DROP_RATE_FERTILIZER {
-                add_resource_or_consign(inventory, shop, total_points, ItemType::Fertilizer);
+                return Some(add_resource_or_consign(inventory, shed, shop, total_points, ItemType::Fertilizer));
             }
         }
-        TileType::Empty => {}
+        TileType::Ingredient | TileType::Empty => {}
     }
+    None
 }
 
 pub fn inventory_count(inventory: &Inventory, target: ItemType) -> u32 {
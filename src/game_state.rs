@@ -1,21 +1,48 @@
 use macroquad::prelude::*;
+use crate::board::{Board, BoardShape};
+use crate::board_input::BoardDrag;
+use crate::board_moves::{self, SwapMove};
 use crate::economy;
-use crate::match_logic::{self, GemParticle, MatchCell};
+use crate::match_logic::{self, GemParticle, MatchCell, MatchGroup, SpecialSpawn};
 use crate::render;
+use crate::scoring::{self, ScoreBreakdown};
 use crate::tile::*; // Import our neighbor, the Tile
-use crate::inventory::Inventory;
+use crate::inventory::{Inventory, ItemType, STACK_MAX};
 use crate::shop::Shop;
 use crate::garden::Garden;
+use crate::level_rules::FailReason;
+use crate::levels::LevelDef;
+use crate::buffs::ActiveBuff;
+use crate::discard::{DiscardConfirm, DiscardNote};
+use crate::modifiers::ActiveModifier;
+use crate::targeting::ArmedItem;
+use crate::objectives::ObjectiveTracker;
+use crate::popups::{self, FloatingPopup};
+use crate::replay::{ReplayPlayer, ReplayRecorder};
+use crate::rng::GameRng;
+use crate::settings::Settings;
+use crate::shed::SHED_SLOTS;
+use crate::undo::UndoSnapshot;
 use crate::ui_layout::{
     Layout,
 };
 
-pub const GRID_WIDTH: usize = 8;
+pub const GRID_WIDTH: usize = 8; // default board size when a level doesn't say
 pub const GRID_HEIGHT: usize = 8;
-pub const TILE_SIZE: f32 = 64.0;
 pub const LEVELS_PER_SET: i32 = 3; // Shop appears every 3 levels
 pub const LEVEL_TARGET_STEP: i32 = 1200;
 pub const MATCH_CLEAR_DELAY: f32 = 0.12;
+pub const SWAP_DURATION: f32 = 0.16; // two tiles sliding past each other, each way
+// --- Falling tiles (speeds in tiles, scaled by the board's tile size) ---
+pub const FALL_GRAVITY: f32 = 45.0;          // tiles per second squared
+pub const FALL_MAX_SPEED: f32 = 16.0;        // tiles per second
+pub const FALL_BOUNCE: f32 = 0.18;           // share of landing speed kept as the bounce
+pub const FALL_BOUNCE_MIN_SPEED: f32 = 4.0;  // slower landings just stop
+pub const FALL_COLUMN_STAGGER: f32 = 0.035;  // seconds between neighbouring columns starting to fall
+pub const SLIDE_SPEED: f32 = 12.0;           // tiles per second, for offsets that aren't falls
+pub const SHUFFLE_DURATION: f32 = 0.9; // dead-board shuffle: shrink out, swap, grow back
+pub const HINT_IDLE_DELAY: f32 = 5.0; // seconds without input before a hint shows
+pub const EXOTIC_ROSE_LEAF_BONUS: i32 = 25; // Rose set: leaves per cleared Exotic
 // --- Leaf sway constants (Forest Floor biome) ---
 pub const LEAF_SWAY_SPEED: f32    = 0.55;  // Hz — primary oscillator
 pub const LEAF_SWAY_SPEED_2: f32  = 0.85;  // Hz — secondary oscillator
@@ -74,17 +101,58 @@ pub enum GardenTool {
     Fertilize,
 }
 
+impl GardenTool {
+    pub const ALL: [GardenTool; 5] = [
+        GardenTool::Water,
+        GardenTool::PlantSun,
+        GardenTool::PlantMoon,
+        GardenTool::PlantEssence,
+        GardenTool::Fertilize,
+    ];
+
+    /// The inventory item the tool spends.
+    pub fn item(self) -> ItemType {
+        match self {
+            GardenTool::Water => ItemType::WateringCan,
+            GardenTool::PlantSun => ItemType::SeedDay,
+            GardenTool::PlantMoon => ItemType::SeedNight,
+            GardenTool::PlantEssence => ItemType::MoonbloomEssence,
+            GardenTool::Fertilize => ItemType::Fertilizer,
+        }
+    }
+}
+
+/// Garden drawer buttons in satchel order. Tools with nothing carried trail
+/// behind in their usual order.
+pub fn drawer_tools(inventory: &Inventory) -> [GardenTool; 5] {
+    let mut tools = GardenTool::ALL;
+    tools.sort_by_key(|tool| inventory.slots.iter().position(|slot| slot.item == Some(tool.item())).unwrap_or(usize::MAX));
+    tools
+}
+
 #[allow(dead_code)]
-#[derive(PartialEq)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum GamePhase {
     Playing,
     LevelTransition, // The "Fog Cleared" choice screen
+    LevelFailed,     // Out of moves or time; retry the level
     Shop,            // The Tarquin Screen
     Garden,          // Tend plants, visit Bagira consignment
     Hunt,            // Scrub mouse mini-game
     BossHunt,        // Biome-boundary boss encounter
 }
 
+/// Two tiles sliding into each other's cells. The grid already holds the
+/// swapped tiles; the tween only moves where they're drawn.
+#[derive(Clone, Copy, Debug)]
+pub struct SwapAnim {
+    pub from: (usize, usize),
+    pub to: (usize, usize),
+    pub timer: f32,
+    pub reverting: bool, // strict mode sliding a swap that made no match back
+    pub free: bool,      // a Swap Twine swap: no move spent, nothing charged
+}
+
 /// Gem textures for a single biome. Biomes (in order):
 /// 0 - Forest Floor | 1 - Deep Cave    | 2 - Volcanic Rift | 3 - Frozen Tundra
 /// 4 - Ocean Trench | 5 - Sky Realm    | 6 - Fungal Wastes  | 7 - Celestial Void
@@ -98,8 +166,15 @@ pub struct BiomeTextures {
 }
 
 pub struct GameState {
-    pub grid: [[Tile; GRID_HEIGHT]; GRID_WIDTH],
+    pub grid: Board,
     pub selected: Option<(usize, usize)>,
+    pub cursor: Option<(usize, usize)>, // keyboard cursor; hidden until a cursor key is pressed
+    pub armed: Option<ArmedItem>,       // item waiting for a board pick
+    pub discard_confirm: Option<DiscardConfirm>, // a valuable sale awaiting yes or no
+    pub discard_note: Option<DiscardNote>,       // Bagira's reply to the last sale
+    pub inventory_open: bool,                    // the inventory screen is up
+    pub inventory_drag: Option<usize>,           // slot being dragged on it
+    pub drag: Option<BoardDrag>,
 
     // Asset Storage
     pub biome_sets: Vec<BiomeTextures>,
@@ -112,11 +187,22 @@ pub struct GameState {
 
     // Match juice state
     pub pending_matches: Vec<MatchCell>,
+    pub pending_groups: Vec<MatchGroup>,
+    pub pending_specials: Vec<SpecialSpawn>,
+    pub last_swap: Option<[(usize, usize); 2]>,
+    pub swap_anim: Option<SwapAnim>,
+    pub settling: bool, // a clear's refill is still falling; matches wait for it to land
     pub particles: Vec<GemParticle>,
+    pub popups: Vec<FloatingPopup>,
     pub clear_timer: f32,
     pub cascade_pulse: f32,
     pub pulse_color: Color,
     pub clear_was_cascade: bool,
+    pub chain: u32,                 // cascade depth of the clear in flight; 0 is the swap's own
+    pub move_score: ScoreBreakdown, // points from the current (or last) move
+    pub shuffle_timer: f32, // counts down while a dead board is reshuffled
+    pub idle_timer: f32,    // seconds since the last board input
+    pub hint: Option<SwapMove>,
     
     // --- THE BACKEND VARIABLES ---
     pub total_points: i32, // Lifetime score (The "Level" Bar)
@@ -124,15 +210,38 @@ pub struct GameState {
     
     pub target: i32,       // Points needed to clear fog
     pub level: i32,
+    pub levels: Vec<LevelDef>, // from the level file; empty means the built-in progression
+    pub objectives: Vec<ObjectiveTracker>, // this level's goals beyond the score target
+    pub moves_made: u32,                   // swaps made this level
+    pub level_start_points: i32,           // total_points when the level began; restored on retry
+    pub level_start_spent: i32,            // spent_points then, likewise
+    pub level_start_illicit: i32,          // illegal_move_cost then, likewise
+    pub time_left: Option<f32>,            // timed levels only, in seconds
+    pub finale_charges: u32,               // line blasts still to fire from spare moves/time
+    pub fail_reason: Option<FailReason>,
+    pub board_stuck: bool, // no reroll made the board playable; the level is lost
     pub phase: GamePhase,
     pub illegal_move_cost: i32,
+    pub undo: Option<UndoSnapshot>, // the game before the last move, while it can still be undone
+    pub undos_used: u32,            // this level; each one raises the fee
+    pub modifiers: Vec<ActiveModifier>, // board items in effect
+    pub buffs: Vec<ActiveBuff>,         // food eaten and not yet worn off
     pub inventory: Inventory,
     pub shop: Shop,
     pub garden: Garden,
+    pub shed: Inventory, // garden storage; reached only from the garden
     
+    pub settings: Settings,
+    pub rng: GameRng,
+    pub frame: u64, // update ticks since launch, timestamps replay actions
+    pub recorder: Option<ReplayRecorder>,
+    pub replay: Option<ReplayPlayer>,
+    pub replay_clock: Option<i64>, // garden time at frame 0 once recording or replaying
+
     // Garden UI tool mode
     pub garden_selected_tool: Option<GardenTool>,
     pub garden_drawer_open: bool,
+    pub shed_open: bool,
     
     // NEW FLAG: Are we in "Overtime"?
     pub is_farming: bool,
@@ -142,18 +251,20 @@ impl GameState {
     pub fn new(
         biome_sets: Vec<BiomeTextures>,
         garden_bg_texture: Texture2D,
+        seed: u64,
+        levels: Vec<LevelDef>,
     ) -> Self {
         debug_assert!(!biome_sets.is_empty(), "biome_sets must not be empty — GameState::new requires at least one BiomeTextures entry");
-        let mut grid = [[Tile { kind: TileType::Empty, offset_y: 0.0 }; GRID_HEIGHT]; GRID_WIDTH];
-        for column in grid.iter_mut() {
-            for tile in column.iter_mut() {
-                *tile = Tile::new_random();
-            }
-        }
-
         let mut game = GameState {
-            grid,
+            grid: Board::new(BoardShape::rect(GRID_WIDTH, GRID_HEIGHT)),
             selected: None,
+            cursor: None,
+            armed: None,
+            discard_confirm: None,
+            discard_note: None,
+            inventory_open: false,
+            inventory_drag: None,
+            drag: None,
             biome_sets,
             garden_bg_texture,
             leaves_main_texture: None,
@@ -162,45 +273,76 @@ impl GameState {
             cave_tree_texture: None,
             cave_nast_texture: None,
             pending_matches: vec![],
+            pending_groups: vec![],
+            pending_specials: vec![],
+            last_swap: None,
+            swap_anim: None,
+            settling: false,
             particles: vec![],
+            popups: vec![],
             clear_timer: 0.0,
             cascade_pulse: 0.0,
             pulse_color: WHITE,
             clear_was_cascade: false,
+            chain: 0,
+            move_score: ScoreBreakdown::default(),
+            shuffle_timer: 0.0,
+            idle_timer: 0.0,
+            hint: None,
             total_points: 0,
             spent_points: 0,
-            target: LEVEL_TARGET_STEP,
+            target: 0,
             level: 1,
+            levels,
+            objectives: vec![],
+            moves_made: 0,
+            level_start_points: 0,
+            level_start_spent: 0,
+            level_start_illicit: ILLEGAL_MOVE_COST_START,
+            time_left: None,
+            finale_charges: 0,
+            fail_reason: None,
+            board_stuck: false,
             phase: GamePhase::Playing,
             illegal_move_cost: ILLEGAL_MOVE_COST_START,
+            undo: None,
+            undos_used: 0,
+            modifiers: vec![],
+            buffs: vec![],
             inventory: Inventory::new(),
             shop: Shop::new(),
             garden: Garden::new(),
+            shed: Inventory::with_capacity(SHED_SLOTS, STACK_MAX),
+            settings: Settings::new(),
+            rng: GameRng::new(seed),
+            frame: 0,
+            recorder: None,
+            replay: None,
+            replay_clock: None,
             garden_selected_tool: None,
             garden_drawer_open: false,
+            shed_open: false,
             is_farming: false,
         };
 
-        // Clear any initial matches immediately so the opening board starts stable.
-        loop {
-            let matches = game.find_matches();
-            if matches.is_empty() {
-                break;
-            }
-            game.clear_matches_immediately(matches);
-            game.apply_gravity();
-        }
+        game.target = game.target_for_level(1);
+        game.reset_board();
+        game.begin_level();
 
         game
     }
 
+    pub(crate) fn layout(&self) -> Layout {
+        Layout::compute(self.grid.width(), self.grid.height())
+    }
+
     // Helper to calculate "Wallet" (Leaves)
     pub fn get_leaves_wallet(&self) -> i32 {
         self.total_points - self.spent_points
     }
 
     pub fn charge_illegal_move(&mut self) {
-        self.spent_points += self.illegal_move_cost;
+        self.spent_points += self.illicit_charge();
         self.illegal_move_cost += ILLEGAL_MOVE_COST_STEP;
     }
 
@@ -212,43 +354,78 @@ impl GameState {
         !self.pending_matches.is_empty()
     }
 
-    pub(crate) fn find_matches(&self) -> Vec<MatchCell> {
+    /// Nothing on the board is moving or waiting to resolve, so items and
+    /// undo can act on it.
+    pub(crate) fn board_idle(&self) -> bool {
+        self.phase == GamePhase::Playing
+            && !self.is_clearing()
+            && !self.is_swapping()
+            && !self.is_settling()
+            && !self.is_shuffling()
+            && !self.in_finale()
+    }
+
+    pub(crate) fn find_matches(&self) -> Vec<MatchGroup> {
         match_logic::find_matches(&self.grid)
     }
 
-    fn spawn_match_particles(&mut self, matches: &[MatchCell]) {
-        let layout = Layout::compute(GRID_WIDTH, GRID_HEIGHT);
-        match_logic::spawn_match_particles(&self.grid, &mut self.particles, matches, &layout);
+    fn spawn_match_particles(&mut self, groups: &[MatchGroup], cleared: &[MatchCell]) {
+        let layout = self.layout();
+        match_logic::spawn_match_particles(&self.grid, &mut self.particles, groups, cleared, &layout, &mut self.rng.cosmetic);
     }
 
-    pub(crate) fn begin_match_clear(&mut self, matches: Vec<MatchCell>, is_cascade: bool) {
-        if matches.is_empty() {
+    /// Start the clear animation for `groups`. `triggered` holds extra cells
+    /// cleared outside any group (e.g. a color-clear swap).
+    pub(crate) fn begin_match_clear(&mut self, groups: Vec<MatchGroup>, triggered: Vec<MatchCell>, is_cascade: bool) {
+        if groups.is_empty() && triggered.is_empty() {
             return;
         }
 
+        // Specials are decided from the groups before blasts widen the clear set.
+        let swap = if is_cascade { None } else { self.last_swap };
+        self.pending_specials = match_logic::find_special_spawns(&groups, swap);
+        let mut cells = match_logic::group_cells(&self.grid, &groups);
+        for cell in triggered {
+            if !cells.iter().any(|(x, y, _)| (*x, *y) == (cell.0, cell.1)) {
+                cells.push(cell);
+            }
+        }
+        let cells = if ExoticTwist::for_biome(self.biome_index()) == ExoticTwist::Mushroom {
+            match_logic::expand_exotic_spores(&self.grid, cells)
+        } else {
+            cells
+        };
+        let cells = match_logic::expand_special_activations(&self.grid, cells);
+
         self.clear_timer = MATCH_CLEAR_DELAY;
         self.clear_was_cascade = is_cascade;
-        self.pulse_color = match_logic::tile_particle_color(matches[0].2);
-        if matches.len() >= 4 || is_cascade {
+        self.pulse_color = match_logic::tile_particle_color(groups.first().map_or(cells[0].2, |group| group.kind));
+        if groups.iter().any(|group| group.length >= 4) || groups.len() > 1 || is_cascade {
             self.cascade_pulse = 1.0;
         }
-        self.spawn_match_particles(&matches);
-        self.pending_matches = matches;
+        self.spawn_match_particles(&groups, &cells);
+        self.pending_matches = cells;
+        self.pending_groups = groups;
     }
 
-    fn clear_matches_immediately(&mut self, matches: Vec<MatchCell>) {
-        if matches.is_empty() {
+    fn clear_matches_immediately(&mut self, groups: Vec<MatchGroup>) {
+        if groups.is_empty() {
             return;
         }
 
-        self.total_points += matches.len() as i32 * 10;
-        for (x, y, kind) in matches {
-            self.grid[x][y].kind = TileType::Empty;
+        let cells = match_logic::group_cells(&self.grid, &groups);
+        self.total_points += match_logic::score_for_clear(&groups, &cells);
+        let drop_scale = self.drop_rate_scale();
+        for (x, y, kind) in cells {
+            self.grid[x][y] = Tile::EMPTY;
             economy::roll_resource_drop(
                 kind,
                 &mut self.inventory,
+                self.settings.overflow_to_shed.then_some(&mut self.shed),
                 &mut self.shop,
                 &mut self.total_points,
+                &mut self.rng.drops,
+                drop_scale,
             );
         }
     }
@@ -258,41 +435,196 @@ impl GameState {
             return;
         }
 
-        let matches = std::mem::take(&mut self.pending_matches);
+        let cells = std::mem::take(&mut self.pending_matches);
+        let groups = std::mem::take(&mut self.pending_groups);
         self.clear_timer = 0.0;
-        self.total_points += matches.len() as i32 * 10;
-
-        for (x, y, kind) in matches {
-            self.grid[x][y].kind = TileType::Empty;
-            economy::roll_resource_drop(
+        let clear_score = scoring::score_clear(&groups, &cells, self.chain);
+        let cascade_bonus = self.cascade_bonus(self.chain);
+        let mint_bonus = self.take_mint_bonus();
+        self.total_points += clear_score.total + cascade_bonus + mint_bonus;
+        self.move_score.clears.push(clear_score);
+        self.move_score.modifier_bonus += cascade_bonus;
+        self.move_score.food_bonus += mint_bonus;
+        let twist = ExoticTwist::for_biome(self.biome_index());
+        self.record_cleared_tiles(&cells);
+
+        let mut exotic_bonus = 0;
+        let mut drops = vec![];
+        let drop_scale = self.drop_rate_scale();
+        for &(x, y, kind) in &cells {
+            self.grid[x][y] = Tile::EMPTY;
+            if kind == TileType::Exotic && twist == ExoticTwist::Rose {
+                exotic_bonus += EXOTIC_ROSE_LEAF_BONUS;
+            }
+            if let Some(drop) = economy::roll_resource_drop(
                 kind,
                 &mut self.inventory,
+                self.settings.overflow_to_shed.then_some(&mut self.shed),
                 &mut self.shop,
                 &mut self.total_points,
-            );
+                &mut self.rng.drops,
+                drop_scale,
+            ) {
+                drops.push((x, y, drop));
+            }
+        }
+        self.total_points += exotic_bonus;
+        self.move_score.exotic_bonus += exotic_bonus;
+        self.spawn_clear_popups(&cells, &clear_score, exotic_bonus + cascade_bonus + mint_bonus, &drops);
+
+        for (x, y, kind, gem) in std::mem::take(&mut self.pending_specials) {
+            self.grid[x][y] = Tile { kind, special: Some(gem), ..Tile::EMPTY };
+        }
+
+        if twist == ExoticTwist::Eye {
+            self.apply_exotic_gaze(&cells, &groups);
         }
 
         self.apply_gravity();
+        self.settling = true;
+    }
+
+    /// Waiting for falling tiles to land before the board is judged.
+    pub(crate) fn is_settling(&self) -> bool {
+        self.settling
+    }
+
+    /// Settle the board once the last tile has landed.
+    pub(crate) fn update_settling(&mut self) {
+        if !self.tiles_falling() {
+            self.settling = false;
+            self.settle_board();
+        }
+    }
 
-        let next_matches = self.find_matches();
-        if !next_matches.is_empty() {
-            self.begin_match_clear(next_matches, true);
+    /// Once tiles have moved: deliver ingredients, then either start the next
+    /// cascade or check the board still has a move.
+    pub(crate) fn settle_board(&mut self) {
+        if self.deliver_ingredients() {
+            // Let the delivered columns fall before looking for matches.
+            self.settling = true;
+            return;
+        }
+
+        let next_groups = self.find_matches();
+        if !next_groups.is_empty() {
+            // A cascade is past taking back.
+            self.undo = None;
+            self.chain += 1;
+            self.begin_match_clear(next_groups, vec![], true);
         } else {
             self.clear_was_cascade = false;
+            if !board_moves::has_valid_move(&self.grid) {
+                self.shuffle_timer = SHUFFLE_DURATION;
+            }
+        }
+    }
+
+    pub(crate) fn is_shuffling(&self) -> bool {
+        self.shuffle_timer > 0.0
+    }
+
+    /// Advance the dead-board shuffle; tiles are rearranged at the midpoint
+    /// while they are shrunk out of view.
+    pub(crate) fn update_shuffle(&mut self, delta: f32) {
+        let halfway = SHUFFLE_DURATION * 0.5;
+        let before = self.shuffle_timer;
+        self.shuffle_timer = (self.shuffle_timer - delta).max(0.0);
+        if before > halfway && self.shuffle_timer <= halfway {
+            self.settle_dead_board();
+            self.selected = None;
+            self.reset_idle_hint();
+        }
+    }
+
+    pub(crate) fn reset_idle_hint(&mut self) {
+        self.idle_timer = 0.0;
+        self.hint = None;
+    }
+
+    /// Count idle time on a settled board and pick a hint once it runs long.
+    pub(crate) fn update_idle_hint(&mut self, delta: f32) {
+        if !self.settings.hints_enabled {
+            self.reset_idle_hint();
+            return;
+        }
+        self.idle_timer += delta;
+        if self.idle_timer >= HINT_IDLE_DELAY && self.hint.is_none() {
+            self.hint = board_moves::best_hint_move(&self.grid);
+        }
+    }
+
+    /// Reshuffle until the board has at least one legal swap and no ready-made
+    /// matches, rerolling the tiles when no order of them works. Free for the
+    /// player: no illicit-move charge applies. The board's shape and spawn
+    /// table stay as they are; after a bounded number of tries this gives up.
+    pub(crate) fn ensure_playable_board(&mut self) -> Result<(), String> {
+        let playable = |game: &Self| game.find_matches().is_empty() && board_moves::has_valid_move(&game.grid);
+        if playable(self) {
+            return Ok(());
+        }
+        for _ in 0..board_moves::REROLL_MAX_ATTEMPTS {
+            for _ in 0..board_moves::SHUFFLE_MAX_ATTEMPTS {
+                board_moves::shuffle_tiles(&mut self.grid, &mut self.rng.board);
+                if playable(self) {
+                    return Ok(());
+                }
+            }
+            // These tiles can't make a playable board in any order; reroll them.
+            self.fill_board();
+        }
+        Err(format!("level {}: no playable board after {} rerolls", self.level, board_moves::REROLL_MAX_ATTEMPTS))
+    }
+
+    /// Make the board playable, or mark it stuck so the level is lost and can
+    /// be retried on a fresh board.
+    fn settle_dead_board(&mut self) {
+        self.board_stuck = match self.ensure_playable_board() {
+            Ok(()) => false,
+            Err(error) => {
+                eprintln!("{}", error);
+                true
+            }
+        };
+    }
+
+    /// Eye-set Exotics turn their surviving neighbours into the colour they
+    /// matched as, which tends to set up the next cascade.
+    fn apply_exotic_gaze(&mut self, cells: &[MatchCell], groups: &[MatchGroup]) {
+        for &(x, y, kind) in cells {
+            if kind != TileType::Exotic {
+                continue;
+            }
+            let Some(color) = groups
+                .iter()
+                .find(|group| group.kind != TileType::Exotic && group.contains(x, y))
+                .map(|group| group.kind)
+            else {
+                continue;
+            };
+            for (nx, ny) in self.grid.orthogonal_neighbours(x, y) {
+                let neighbour = &mut self.grid[nx][ny];
+                if neighbour.kind.is_gem() && neighbour.kind != TileType::Exotic {
+                    neighbour.kind = color;
+                }
+            }
         }
     }
 
     pub(crate) fn update_match_effects(&mut self, delta: f32) {
         match_logic::update_match_effects(&mut self.particles, &mut self.cascade_pulse, delta);
+        popups::update_popups(&mut self.popups, delta);
     }
 
     #[cfg(feature = "dev")]
     pub(crate) fn save_progress(&self) {
-        let data = format!("{}\n{}\n{}\n{}\n",
+        let data = format!("{}\n{}\n{}\n{}\n{}\n{}\n",
             self.level,
             self.total_points,
             self.spent_points,
             self.illegal_move_cost,
+            self.inventory.slot_count(),
+            self.inventory.stack_max(),
         );
         let _ = std::fs::write("dev_save.txt", data);
     }
@@ -308,89 +640,189 @@ impl GameState {
                 lines.next().and_then(|s| s.parse::<i32>().ok()),
             ) {
                 self.level         = l;
-                self.target        = l * LEVEL_TARGET_STEP;
+                self.target        = self.target_for_level(l);
                 self.total_points  = tp;
                 self.spent_points  = sp;
                 self.illegal_move_cost = ic;
+                // Saves from before the satchel upgrades stop here. If what's
+                // carried now wouldn't fit the saved satchel, it stays as is.
+                let slots = lines.next().and_then(|s| s.parse::<usize>().ok()).unwrap_or(crate::inventory::INVENTORY_SLOTS);
+                let stack_max = lines.next().and_then(|s| s.parse::<u32>().ok()).unwrap_or(crate::inventory::STACK_MAX);
+                self.inventory.set_capacity(slots, stack_max);
                 self.is_farming    = false;
                 self.phase         = GamePhase::Playing;
                 self.reset_board();
+                self.begin_level();
             }
         }
     }
 
-    #[cfg_attr(not(feature = "dev"), allow(dead_code))]
-    pub(crate) fn reset_board(&mut self) {
-        for x in 0..GRID_WIDTH {
-            for y in 0..GRID_HEIGHT {
-                self.grid[x][y] = crate::tile::Tile::new_random();
+    /// Random tiles in every playable cell; holes stay empty and blockers stay
+    /// put. Ingredients and special gems are kept, so a reroll mid-level
+    /// can't take away what an objective or an earlier match left behind.
+    fn fill_board(&mut self) {
+        for (x, y) in self.grid.cells() {
+            let tile = self.grid[x][y];
+            if tile.kind == TileType::Ingredient || tile.special.is_some() {
+                continue;
             }
+            let blocker = tile.blocker;
+            self.grid[x][y] = self.grid.spawn_table().roll(&mut self.rng.board);
+            self.grid[x][y].blocker = blocker;
+        }
+    }
+
+    /// Designed levels always start on their own board. Built-in levels keep
+    /// the tiles in play unless the board shape changes, as they always have.
+    pub(crate) fn apply_level_board(&mut self) {
+        if self.level_def(self.level).is_some() || *self.grid.shape() != self.board_shape_for_level(self.level) {
+            self.reset_board();
         }
+    }
+
+    /// A fresh board for the current level, blockers included.
+    pub(crate) fn reset_board(&mut self) {
+        let blockers = self.level_def(self.level).map(|def| def.blockers.clone()).unwrap_or_default();
+        self.rebuild_board(self.board_shape_for_level(self.level), &blockers);
+    }
+
+    /// Fresh tiles on `shape` with no ready-made matches and at least one
+    /// legal swap.
+    pub(crate) fn rebuild_board(&mut self, shape: BoardShape, blockers: &[(usize, usize)]) {
+        self.grid = Board::new(shape);
+        self.apply_spawn_table();
+        self.fill_board();
         loop {
             let matches = self.find_matches();
             if matches.is_empty() { break; }
             self.clear_matches_immediately(matches);
             self.apply_gravity();
         }
+        for &(x, y) in blockers {
+            self.grid[x][y].blocker = true;
+        }
+        self.settle_dead_board();
+        self.shuffle_timer = 0.0;
         self.pending_matches = vec![];
+        self.pending_groups = vec![];
+        self.pending_specials = vec![];
+        self.swap_anim = None;
+        self.settling = false;
+        self.undo = None;
         self.particles = vec![];
+        self.popups = vec![];
         self.clear_timer = 0.0;
         self.selected = None;
+        self.cursor = None;
+        self.armed = None;
+        self.drag = None;
+        self.reset_idle_hint();
     }
 
     pub fn apply_gravity(&mut self) {
-        for x in 0..GRID_WIDTH {
-            // Step 1: Scan from BOTTOM to TOP (y=7 down to y=0)
-            for y in (0..GRID_HEIGHT).rev() {
+        let tile_size = self.layout().tile_size;
+        let mut stagger = 0.0;
+        for x in 0..self.grid.width() {
+            // Only playable cells take part; tiles fall straight past holes.
+            let rows = self.grid.column_cells(x);
+            let mut column_moved = false;
+
+            // Step 1: Scan from BOTTOM to TOP
+            for (index, &y) in rows.iter().enumerate().rev() {
                 if self.grid[x][y].kind == TileType::Empty {
-                    // Look for a non-empty tile ABOVE (indices 0 to y-1)
+                    // Look for a non-empty tile ABOVE
                     // We use .rev() to find the CLOSEST tile first
-                    if let Some(source_y) = (0..y).rev().find(|&sy| self.grid[x][sy].kind != TileType::Empty) {
+                    if let Some(&source_y) = rows[..index].iter().rev().find(|&&sy| self.grid[x][sy].kind != TileType::Empty) {
                         let distance_moved = y - source_y;
                         self.grid[x][y] = self.grid[x][source_y];
-                        self.grid[x][y].offset_y = -(distance_moved as f32 * TILE_SIZE);
+                        // A tile already in the air keeps falling from where it is.
+                        self.grid[x][y].offset_y -= distance_moved as f32 * tile_size;
                         self.grid[x][source_y].kind = TileType::Empty;
+                        column_moved = true;
                     }
                 }
             }
-            
-            // Step 2: Fill the remaining empty slots at the top with new tiles
-            for y in 0..GRID_HEIGHT {
-                if self.grid[x][y].kind == TileType::Empty {
-                    self.grid[x][y] = Tile::new_random();
-                    self.grid[x][y].offset_y = -(TILE_SIZE * 4.0); // Spawn from above
+
+            // Step 2: Fill the remaining empty slots at the top with new tiles,
+            // stacked just above the column so they fall in as one block.
+            let empty: Vec<usize> = rows.iter().copied().filter(|&y| self.grid[x][y].kind == TileType::Empty).collect();
+            if let Some(&top) = rows.first() {
+                for (index, &y) in empty.iter().enumerate() {
+                    let spawn_row = top as f32 - (empty.len() - index) as f32;
+                    self.grid[x][y] = self.grid.spawn_table().roll(&mut self.rng.board);
+                    self.grid[x][y].offset_y = (spawn_row - y as f32) * tile_size;
+                    column_moved = true;
                 }
             }
+
+            // Step 3: Columns start falling one after another, left to right.
+            if column_moved {
+                for &y in &rows {
+                    let tile = &mut self.grid[x][y];
+                    if tile.offset_y < 0.0 && tile.fall_velocity == 0.0 && tile.fall_delay <= 0.0 {
+                        tile.fall_delay = stagger;
+                    }
+                }
+                stagger += FALL_COLUMN_STAGGER;
+            }
         }
     }
 
-    // Animate tile offsets back to 0
+    /// Move tiles back into their cells: sideways slides ease in, falls
+    /// accelerate under gravity and bounce a little on landing.
     pub fn animate_tiles(&mut self, delta: f32) {
-        let animation_speed = 800.0; // Pixels per second
-        for x in 0..GRID_WIDTH {
-            for y in 0..GRID_HEIGHT {
-                if self.grid[x][y].offset_y != 0.0 {
-                    let move_amount = animation_speed * delta;
-                    if self.grid[x][y].offset_y < 0.0 {
-                        self.grid[x][y].offset_y += move_amount;
-                        if self.grid[x][y].offset_y > 0.0 {
-                            self.grid[x][y].offset_y = 0.0;
-                        }
+        let tile_size = self.layout().tile_size;
+        let slide_speed = SLIDE_SPEED * tile_size;
+        for column in self.grid.columns_mut() {
+            for tile in column.iter_mut() {
+                if tile.offset_x != 0.0 {
+                    let move_amount = (slide_speed * delta).min(tile.offset_x.abs());
+                    tile.offset_x -= move_amount * tile.offset_x.signum();
+                }
+
+                if tile.fall_delay > 0.0 {
+                    tile.fall_delay = (tile.fall_delay - delta).max(0.0);
+                    continue;
+                }
+                if tile.offset_y > 0.0 && tile.fall_velocity == 0.0 {
+                    // Pushed below its cell (a drag or a swap): slide back up.
+                    tile.offset_y = (tile.offset_y - slide_speed * delta).max(0.0);
+                    continue;
+                }
+                if tile.offset_y == 0.0 && tile.fall_velocity == 0.0 {
+                    continue;
+                }
+
+                tile.fall_velocity = (tile.fall_velocity + FALL_GRAVITY * tile_size * delta).min(FALL_MAX_SPEED * tile_size);
+                tile.offset_y += tile.fall_velocity * delta;
+                if tile.offset_y >= 0.0 {
+                    tile.offset_y = 0.0;
+                    tile.fall_velocity = if tile.fall_velocity > FALL_BOUNCE_MIN_SPEED * tile_size {
+                        -tile.fall_velocity * FALL_BOUNCE
                     } else {
-                        self.grid[x][y].offset_y -= move_amount;
-                        if self.grid[x][y].offset_y < 0.0 {
-                            self.grid[x][y].offset_y = 0.0;
-                        }
-                    }
+                        0.0
+                    };
                 }
             }
         }
     }
 
+    /// True while any tile is still on its way into its cell.
+    pub(crate) fn tiles_falling(&self) -> bool {
+        self.grid
+            .tiles()
+            .any(|tile| tile.offset_y != 0.0 || tile.fall_velocity != 0.0 || tile.fall_delay > 0.0)
+    }
+
     pub fn update(&mut self) {
+        self.frame += 1;
+        self.update_replay();
+        self.update_discard_note(get_frame_time());
+
         match self.phase {
             GamePhase::Playing => self.update_playing(),
             GamePhase::LevelTransition => self.update_level_transition(),
+            GamePhase::LevelFailed => self.update_level_failed(),
             GamePhase::Shop => self.update_shop(),
             GamePhase::Garden => self.update_garden(),
             GamePhase::Hunt => self.update_hunt(),
@@ -400,13 +832,18 @@ impl GameState {
 
     pub fn draw(&self) {
         // Biome Background Logic
-        let set_index = (self.level - 1) / LEVELS_PER_SET;
+        let set_index = self.biome_index();
         let bg_color = match set_index { 0 => BLACK, 1 => BLACK, _ => color_u8!(30, 0, 0, 255) };
         clear_background(bg_color);
-        let layout = Layout::compute(GRID_WIDTH, GRID_HEIGHT);
+        let layout = self.layout();
 
         if self.phase == GamePhase::Shop {
-            render::draw_shop_screen(self.get_leaves_wallet());
+            render::draw_shop_screen(
+                self.get_leaves_wallet(),
+                &self.carried_food_lines(),
+                &self.buff_badges(),
+                &self.upgrade_lines(),
+            );
             return;
         }
 
@@ -425,6 +862,9 @@ impl GameState {
                 self.garden_selected_tool,
                 self.garden_drawer_open,
             );
+            self.draw_shed_screen();
+            self.draw_inventory_screen();
+            self.draw_discard_overlays();
             return;
         }
 
@@ -442,18 +882,33 @@ impl GameState {
                     &layout,
                     self.level,
                     self.target,
-                    LEVEL_TARGET_STEP,
+                    self.target_step_for_level(self.level),
                     self.total_points,
                     self.get_leaves_wallet(),
-                    self.illegal_move_cost,
+                    self.illicit_charge(),
                     &self.inventory,
                     self.is_farming,
+                    self.settings.hints_enabled,
+                    self.strict_swaps(),
+                    self.level_forces_strict(),
+                    self.undo_available(),
+                    self.armed,
+                    &[self.modifier_badges(), self.buff_badges()].concat(),
+                    &[self.carried_modifier_lines(), self.carried_food_lines()].concat(),
+                    &self.objective_lines(),
+                    self.level_limit_readout(),
+                    &self.move_score,
                 );
+                self.draw_inventory_screen();
+                self.draw_discard_overlays();
             },
             GamePhase::LevelTransition => {
                 render::draw_level_transition_ui();
             },
+            GamePhase::LevelFailed => {
+                render::draw_level_failed_ui(self.fail_reason.map_or("LEVEL FAILED", |reason| reason.message()));
+            },
             _ => {}
         }
     }
-}
\ No newline at end of file
+}
@@ -1,52 +1,161 @@
 use macroquad::prelude::*;
 
 #[cfg(feature = "dev")]
-use crate::game_state::{LEVELS_PER_SET, LEVEL_TARGET_STEP};
-use crate::game_state::{GamePhase, GameState, GRID_HEIGHT, GRID_WIDTH};
+use crate::board::{BoardShape, SHAPE_PRESETS};
+use crate::game_state::{GamePhase, GameState, SwapAnim, SWAP_DURATION};
+#[cfg(feature = "dev")]
+use crate::inventory::ItemType;
+#[cfg(feature = "dev")]
+use crate::buffs::FOOD_KINDS;
+use crate::match_logic::{self, MatchCell};
+use crate::modifiers::MODIFIER_KINDS;
+use crate::replay::{PhaseAction, ReplayAction};
+use crate::scoring::ScoreBreakdown;
+#[cfg(feature = "dev")]
+use crate::targeting::TARGETED_KINDS;
+use crate::tile::SpecialGem;
 use crate::ui_layout::{
     point_in_rect,
     playing_descend_button_rect,
+    playing_inventory_slot_rect,
+    playing_undo_button_rect,
     playing_visit_garden_button_rect,
     Layout,
 };
 
 impl GameState {
     pub(crate) fn update_playing(&mut self) {
-        let delta = get_frame_time();
+        let delta = self.frame_delta();
         self.animate_tiles(delta);
         self.update_match_effects(delta);
-        let layout = Layout::compute(GRID_WIDTH, GRID_HEIGHT);
+        let layout = self.layout();
+        // A pending sale, then the inventory screen, hold the board's input.
+        let screen_live = self.accepts_live_input() && self.discard_confirm.is_none();
+        let live = screen_live && !self.inventory_open;
+        self.update_discard_confirm();
+        if screen_live && self.inventory_open {
+            self.update_inventory_screen();
+        }
 
         if self.is_clearing() {
             self.clear_timer -= delta;
             if self.clear_timer <= 0.0 {
                 self.finalize_match_clear();
             }
-        } else if is_mouse_button_pressed(MouseButton::Left) {
-            let (mx, my) = mouse_position();
-            self.handle_playing_click(mx, my, &layout);
+        } else if self.is_swapping() {
+            self.update_swap(delta);
+        } else if self.is_settling() {
+            self.update_settling();
+        } else if self.is_shuffling() {
+            self.update_shuffle(delta);
+        } else if self.in_finale() {
+            self.fire_finale_charge();
+        } else {
+            if live {
+                self.handle_board_input(&layout);
+            }
+            self.update_idle_hint(delta);
+        }
+
+        self.tick_level_clock(delta);
+        if !self.is_clearing() && !self.is_swapping() && !self.is_settling() {
+            self.update_objectives();
+        }
+
+        if is_key_pressed(KeyCode::H) {
+            self.settings.hints_enabled = !self.settings.hints_enabled;
+            self.reset_idle_hint();
+        }
+
+        // The setting can't be flipped mid-swap, and a strict level pins it.
+        if live && self.board_idle() && !self.level_forces_strict() && is_key_pressed(KeyCode::T) {
+            self.perform_action(ReplayAction::ToggleStrictSwaps);
+        }
+
+        if live && is_key_pressed(KeyCode::U) && self.undo_available().is_some() {
+            self.perform_action(ReplayAction::Undo);
+        }
+
+        if live {
+            // Board items only go off on a still board; a press mid-cascade
+            // would be refused live but land once a replay catches up.
+            if self.board_idle() {
+                let keys = [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4];
+                for (key, kind) in keys.iter().zip(MODIFIER_KINDS) {
+                    if is_key_pressed(*key) {
+                        self.perform_action(ReplayAction::UseModifier(kind));
+                    }
+                }
+            }
+            self.handle_food_keys();
+            if is_key_pressed(KeyCode::Tab) {
+                self.toggle_inventory_screen();
+            }
+            if self.armed.is_some() && is_key_pressed(KeyCode::Escape) {
+                self.perform_action(ReplayAction::Disarm);
+            }
         }
 
         if self.phase == GamePhase::Playing
             && !self.is_clearing()
-            && self.total_points >= self.target
+            && !self.is_swapping()
+            && !self.is_settling()
+            && !self.is_shuffling()
             && !self.is_farming
         {
-            self.phase = GamePhase::LevelTransition;
+            self.check_level_end();
         }
 
         #[cfg(feature = "dev")]
         if is_key_pressed(KeyCode::B) {
-            let current_biome = (self.level - 1) / LEVELS_PER_SET;
-            let next_level = (current_biome + 1) * LEVELS_PER_SET + 1;
+            let current_biome = self.biome_index();
+            let mut next_level = self.level + 1;
+            while self.biome_for_level(next_level) == current_biome {
+                next_level += 1;
+            }
             self.level = next_level;
-            self.target = next_level * LEVEL_TARGET_STEP;
+            self.target = self.target_for_level(next_level);
             self.total_points = self.target - 1;
             self.spent_points = 0;
             self.reset_illegal_move_cost();
             self.is_farming = false;
             self.phase = GamePhase::Playing;
             self.reset_board();
+            self.begin_level();
+        }
+
+        // Cycle the board through the shaped presets to try them out.
+        #[cfg(feature = "dev")]
+        if is_key_pressed(KeyCode::N) && !self.is_clearing() {
+            let names = SHAPE_PRESETS;
+            let current = names.iter().position(|name| BoardShape::preset(name).as_ref() == Some(self.grid.shape()));
+            let next = match current {
+                Some(index) => names.get(index + 1),
+                None => names.first(),
+            };
+            match next.and_then(|name| BoardShape::preset(name)) {
+                Some(shape) => self.rebuild_board(shape, &[]),
+                None => self.reset_board(),
+            }
+        }
+
+        // One of each board modifier and food, to try them out.
+        #[cfg(feature = "dev")]
+        if is_key_pressed(KeyCode::M) {
+            for kind in MODIFIER_KINDS {
+                self.inventory.push(ItemType::BoardModifier(kind));
+            }
+            for kind in FOOD_KINDS {
+                self.inventory.push(ItemType::FoodBuff(kind));
+            }
+        }
+
+        // And the items aimed at the board.
+        #[cfg(feature = "dev")]
+        if is_key_pressed(KeyCode::K) {
+            for kind in TARGETED_KINDS {
+                self.inventory.push(ItemType::BoardModifier(kind));
+            }
         }
 
         #[cfg(feature = "dev")]
@@ -61,16 +170,35 @@ impl GameState {
     }
 
     pub(crate) fn handle_playing_click(&mut self, mx: f32, my: f32, layout: &Layout) {
+        self.reset_idle_hint();
+
         let (garden_x, garden_y, garden_w, garden_h) = playing_visit_garden_button_rect(layout);
         if point_in_rect(mx, my, garden_x, garden_y, garden_w, garden_h) {
-            self.phase = GamePhase::Garden;
+            self.perform_action(ReplayAction::Phase(PhaseAction::VisitGarden));
+            return;
+        }
+
+        let (undo_x, undo_y, undo_w, undo_h) = playing_undo_button_rect(layout);
+        if point_in_rect(mx, my, undo_x, undo_y, undo_w, undo_h) {
+            if self.undo_available().is_some() {
+                self.perform_action(ReplayAction::Undo);
+            }
             return;
         }
 
+        let slot_count = self.inventory.slot_count();
+        for index in 0..slot_count {
+            let (slot_x, slot_y, slot_w, slot_h) = playing_inventory_slot_rect(layout, index, slot_count);
+            if point_in_rect(mx, my, slot_x, slot_y, slot_w, slot_h) {
+                self.click_inventory_slot(index);
+                return;
+            }
+        }
+
         if self.is_farming {
             let (btn_x, btn_y, btn_w, btn_h) = playing_descend_button_rect(layout);
             if point_in_rect(mx, my, btn_x, btn_y, btn_w, btn_h) {
-                self.phase = GamePhase::LevelTransition;
+                self.perform_action(ReplayAction::Phase(PhaseAction::DescendFromFarming));
                 return;
             }
         }
@@ -78,15 +206,39 @@ impl GameState {
         let gx = ((mx - layout.grid_offset_x) / layout.tile_size).floor() as isize;
         let gy = ((my - layout.grid_offset_y) / layout.tile_size).floor() as isize;
 
-        if gx < 0 || gx >= GRID_WIDTH as isize || gy < 0 || gy >= GRID_HEIGHT as isize {
-            self.selected = None;
+        if gx < 0 || gy < 0 || !self.grid.is_playable(gx as usize, gy as usize) {
+            if self.armed.is_some() {
+                self.perform_action(ReplayAction::Disarm);
+            } else if self.selected.is_some() {
+                self.perform_action(ReplayAction::Deselect);
+            }
             return;
         }
 
-        self.handle_board_selection(gx as usize, gy as usize);
+        self.perform_action(ReplayAction::Select { x: gx as usize, y: gy as usize });
+    }
+
+    /// Right-clicking a satchel chip offers the item to Bagira.
+    pub(crate) fn handle_playing_right_click(&mut self, mx: f32, my: f32, layout: &Layout) {
+        let slot_count = self.inventory.slot_count();
+        for index in 0..slot_count {
+            let (slot_x, slot_y, slot_w, slot_h) = playing_inventory_slot_rect(layout, index, slot_count);
+            if point_in_rect(mx, my, slot_x, slot_y, slot_w, slot_h) {
+                self.request_discard(index);
+                return;
+            }
+        }
     }
 
     pub(crate) fn handle_board_selection(&mut self, gx: usize, gy: usize) {
+        if self.armed.is_some() {
+            self.aim_armed_item(gx, gy);
+            return;
+        }
+        // Brambles hold their tile in place.
+        if self.grid[gx][gy].blocker {
+            return;
+        }
         match self.selected {
             None => self.selected = Some((gx, gy)),
             Some((sx, sy)) => {
@@ -101,20 +253,122 @@ impl GameState {
         }
     }
 
+    /// Swap two tiles and start them sliding. The swap is judged once they land.
     pub(crate) fn resolve_swap(&mut self, sx: usize, sy: usize, gx: usize, gy: usize) {
-        let temp = self.grid[sx][sy];
-        self.grid[sx][sy] = self.grid[gx][gy];
-        self.grid[gx][gy] = temp;
-
-        let matches = self.find_matches();
-        if matches.is_empty() {
-            // Intentionally keep the swap: illicit moves are a mechanic,
-            // so do not revert or deny non-matching swaps here.
+        self.swap_tiles((sx, sy), (gx, gy));
+        self.last_swap = Some([(sx, sy), (gx, gy)]);
+        self.swap_anim = Some(SwapAnim { from: (sx, sy), to: (gx, gy), timer: SWAP_DURATION, reverting: false, free: false });
+        self.selected = None;
+    }
+
+    /// Swap Twine: any two tiles trade places, however far apart.
+    pub(crate) fn start_free_swap(&mut self, a: (usize, usize), b: (usize, usize)) {
+        self.swap_tiles(a, b);
+        self.last_swap = Some([a, b]);
+        self.swap_anim = Some(SwapAnim { from: a, to: b, timer: SWAP_DURATION, reverting: false, free: true });
+        self.selected = None;
+    }
+
+    fn swap_tiles(&mut self, (ax, ay): (usize, usize), (bx, by): (usize, usize)) {
+        let temp = self.grid[ax][ay];
+        self.grid[ax][ay] = self.grid[bx][by];
+        self.grid[bx][by] = temp;
+    }
+
+    pub(crate) fn is_swapping(&self) -> bool {
+        self.swap_anim.is_some()
+    }
+
+    /// Slide both tiles from the other's cell into their own, easing in and
+    /// out, then judge the swap.
+    pub(crate) fn update_swap(&mut self, delta: f32) {
+        let Some(mut anim) = self.swap_anim else {
+            return;
+        };
+        anim.timer = (anim.timer - delta).max(0.0);
+        let t = anim.timer / SWAP_DURATION;
+        let eased = t * t * (3.0 - 2.0 * t);
+        let tile_size = self.layout().tile_size;
+        let (ax, ay) = anim.from;
+        let (bx, by) = anim.to;
+        let dx = (bx as f32 - ax as f32) * tile_size * eased;
+        let dy = (by as f32 - ay as f32) * tile_size * eased;
+        self.grid[ax][ay].fall_velocity = 0.0;
+        self.grid[bx][by].fall_velocity = 0.0;
+        self.grid[ax][ay].offset_x = dx;
+        self.grid[ax][ay].offset_y = dy;
+        self.grid[bx][by].offset_x = -dx;
+        self.grid[bx][by].offset_y = -dy;
+
+        if anim.timer > 0.0 {
+            self.swap_anim = Some(anim);
+            return;
+        }
+        self.swap_anim = None;
+        if anim.free {
+            self.finish_free_swap();
+        } else if !anim.reverting {
+            self.finish_swap(anim.from, anim.to);
+        }
+    }
+
+    /// A free swap clears whatever it lines up and is never charged.
+    fn finish_free_swap(&mut self) {
+        let groups = self.find_matches();
+        if groups.is_empty() {
+            self.settle_board();
+            return;
+        }
+        self.chain = 0;
+        self.move_score = ScoreBreakdown::default();
+        self.begin_match_clear(groups, vec![], false);
+    }
+
+    fn finish_swap(&mut self, (sx, sy): (usize, usize), (gx, gy): (usize, usize)) {
+        let groups = self.find_matches();
+        let makes_color_clear = [((gx, gy), (sx, sy)), ((sx, sy), (gx, gy))]
+            .iter()
+            .any(|&((x, y), (tx, ty))| self.grid[x][y].special == Some(SpecialGem::ColorClear) && self.grid[tx][ty].kind.is_gem());
+
+        if groups.is_empty() && !makes_color_clear && self.strict_swaps() {
+            // Strict rules: slide back, no move spent and nothing charged.
+            self.swap_tiles((sx, sy), (gx, gy));
+            self.last_swap = None;
+            self.swap_anim = Some(SwapAnim { from: (sx, sy), to: (gx, gy), timer: SWAP_DURATION, reverting: true, free: false });
+            return;
+        }
+
+        self.snapshot_for_undo((sx, sy), (gx, gy));
+        self.moves_made += 1;
+        self.chain = 0;
+        self.move_score = ScoreBreakdown::default();
+        let mut triggered = self.color_clear_swap_cells((gx, gy), (sx, sy));
+        triggered.extend(self.color_clear_swap_cells((sx, sy), (gx, gy)));
+
+        if groups.is_empty() && triggered.is_empty() {
+            // Outside strict rules the swap is kept: illicit moves are a
+            // mechanic, so do not revert or deny non-matching swaps here.
             self.charge_illegal_move();
+            // The swap may still drop an ingredient into the bottom row.
+            self.settle_board();
         } else {
-            self.begin_match_clear(matches, false);
+            self.begin_match_clear(groups, triggered, false);
         }
+    }
 
-        self.selected = None;
+    /// Swapping a color-clear gem into any tile wipes that tile's kind from the
+    /// board. The gem is spent here so it does not fire again on its own kind.
+    fn color_clear_swap_cells(&mut self, gem_at: (usize, usize), target_at: (usize, usize)) -> Vec<MatchCell> {
+        let (gx, gy) = gem_at;
+        let target_kind = self.grid[target_at.0][target_at.1].kind;
+        if self.grid[gx][gy].special != Some(SpecialGem::ColorClear) || !target_kind.is_gem() {
+            return vec![];
+        }
+
+        self.grid[gx][gy].special = None;
+        match_logic::special_blast_cells(&self.grid, gx, gy, SpecialGem::ColorClear, target_kind)
+            .into_iter()
+            .map(|(x, y)| (x, y, self.grid[x][y].kind))
+            .collect()
     }
-}
\ No newline at end of file
+}
@@ -107,7 +107,7 @@ impl GardenPlot {
     }
 
     /// Advance stage if the timer has elapsed and conditions are met.
-    /// `now_unix` must be UTC unix seconds from system clock.
+    /// `now_unix` is UTC unix seconds, from `GameState::clock_now`.
     /// Returns true if the plot advanced.
     pub fn tick(&mut self, now_unix: i64) -> bool {
         if self.stage.is_terminal() {
@@ -1,5 +1,4 @@
-#![allow(dead_code)]
-
+// Starting capacity. Bagira's upgrades raise both at runtime.
 pub const INVENTORY_SLOTS: usize = 8;
 pub const STACK_MAX: u32 = 10;
 
@@ -28,22 +27,38 @@ pub enum BoardModifierKind {
     IllegalCostCap,     // cap illicit move cost at a fixed max this level
     CascadeBonus,       // bonus points on cascades of 4+
     FogClear,           // removes one boss debuff tile from board
+    TileRemove,         // digs out one picked tile
+    FreeSwap,           // swaps any two picked tiles, free of charge
+    RowClear,           // clears a picked row
 }
 
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub enum FoodBuffKind {
-    // Placeholders — Tarquin free food items
+    // Tarquin free food items, eaten from the inventory (see buffs.rs)
     MintLeaf,    // small leaf bonus on next match resolution
     HoneyBread,  // temporary drop rate boost for one level
     SpicedNut,   // illicit move cost reduced by 50 for next level
 }
 
+/// Where an item lands when the inventory is sorted.
+fn sort_rank(item: ItemType) -> u8 {
+    match item {
+        ItemType::WateringCan => 0,
+        ItemType::SeedDay => 1,
+        ItemType::SeedNight => 2,
+        ItemType::MoonbloomEssence => 3,
+        ItemType::Fertilizer => 4,
+        ItemType::BoardModifier(kind) => 10 + kind as u8,
+        ItemType::FoodBuff(kind) => 30 + kind as u8,
+    }
+}
+
 // --- Inventory Slot ---
 
 #[derive(Clone, Copy, Debug)]
 pub struct InventorySlot {
     pub item: Option<ItemType>,
-    pub count: u32, // 0..=STACK_MAX
+    pub count: u32, // 0..=stack_max
 }
 
 impl InventorySlot {
@@ -54,14 +69,56 @@ impl InventorySlot {
 
 #[derive(Clone, Debug)]
 pub struct Inventory {
-    pub slots: [InventorySlot; INVENTORY_SLOTS],
+    pub slots: Vec<InventorySlot>, // one per slot the satchel has room for
+    stack_max: u32,
 }
 
 impl Inventory {
     pub fn new() -> Self {
+        Self::with_capacity(INVENTORY_SLOTS, STACK_MAX)
+    }
+
+    /// An empty container of any size, e.g. the garden shed.
+    pub fn with_capacity(slots: usize, stack_max: u32) -> Self {
         Self {
-            slots: [InventorySlot::EMPTY; INVENTORY_SLOTS],
+            slots: vec![InventorySlot::EMPTY; slots],
+            stack_max,
+        }
+    }
+
+    pub fn slot_count(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn stack_max(&self) -> u32 {
+        self.stack_max
+    }
+
+    /// Grow to at least `slots` slots and `stack_max` per stack. Capacity
+    /// never shrinks, so nothing carried is ever lost to it.
+    pub fn raise_capacity(&mut self, slots: usize, stack_max: u32) {
+        if slots > self.slots.len() {
+            self.slots.resize(slots, InventorySlot::EMPTY);
         }
+        self.stack_max = self.stack_max.max(stack_max);
+    }
+
+    /// Set capacity to exactly `slots` and `stack_max`, e.g. from a save.
+    /// Returns false and changes nothing if what's carried wouldn't fit: a
+    /// stack in a slot past the end, or taller than the new stack size.
+    #[cfg(any(test, feature = "dev"))]
+    pub fn set_capacity(&mut self, slots: usize, stack_max: u32) -> bool {
+        let fits = self
+            .slots
+            .iter()
+            .enumerate()
+            .all(|(index, slot)| slot.item.is_none() || (index < slots && slot.count <= stack_max));
+        if !fits || slots == 0 || stack_max == 0 {
+            return false;
+        }
+        self.slots.resize(slots, InventorySlot::EMPTY);
+        self.stack_max = stack_max;
+        true
     }
 
     /// Add one unit of an item. Returns true on success.
@@ -69,7 +126,7 @@ impl Inventory {
     pub fn push(&mut self, item: ItemType) -> bool {
         // Try to stack onto an existing slot first
         for slot in self.slots.iter_mut() {
-            if slot.item == Some(item) && slot.count < STACK_MAX {
+            if slot.item == Some(item) && slot.count < self.stack_max {
                 slot.count += 1;
                 return true;
             }
@@ -85,6 +142,81 @@ impl Inventory {
         false
     }
 
+    /// Remove one unit of `item` from the first slot holding it, e.g. when
+    /// it's used up. Returns false if there is none.
+    pub fn take_one(&mut self, item: ItemType) -> bool {
+        match self.slots.iter().position(|slot| slot.item == Some(item)) {
+            Some(index) => self.discard_one(index).is_some(),
+            None => false,
+        }
+    }
+
+    /// Drop the stack in `from` onto `to`: into an empty slot it moves, onto
+    /// the same item it merges up to the stack size (any rest stays behind),
+    /// and onto anything else the two swap places.
+    pub fn move_slot(&mut self, from: usize, to: usize) {
+        if from == to || from >= self.slots.len() || to >= self.slots.len() {
+            return;
+        }
+        let (source, target) = (self.slots[from], self.slots[to]);
+        if source.item.is_some() && source.item == target.item {
+            let moved = source.count.min(self.stack_max.saturating_sub(target.count));
+            self.slots[to].count += moved;
+            self.slots[from].count -= moved;
+            if self.slots[from].count == 0 {
+                self.slots[from] = InventorySlot::EMPTY;
+            }
+        } else {
+            self.slots.swap(from, to);
+        }
+    }
+
+    /// Move half of a stack, rounded down, into the first empty slot.
+    /// Returns false for a single item or a full satchel.
+    pub fn split(&mut self, index: usize) -> bool {
+        let Some(slot) = self.slots.get(index).copied() else {
+            return false;
+        };
+        let half = slot.count / 2;
+        let Some(empty) = self.slots.iter().position(|slot| slot.item.is_none()) else {
+            return false;
+        };
+        if slot.item.is_none() || half == 0 {
+            return false;
+        }
+        self.slots[index].count -= half;
+        self.slots[empty] = InventorySlot { item: slot.item, count: half };
+        true
+    }
+
+    /// Merge matching stacks and order them garden goods first, then board
+    /// items, then food. Empty slots go to the end.
+    pub fn sort(&mut self) {
+        let mut items: Vec<(ItemType, u32)> = vec![];
+        for slot in self.slots.iter() {
+            let Some(item) = slot.item else {
+                continue;
+            };
+            match items.iter_mut().find(|(kind, _)| *kind == item) {
+                Some((_, count)) => *count += slot.count,
+                None => items.push((item, slot.count)),
+            }
+        }
+        items.sort_by_key(|(item, _)| sort_rank(*item));
+
+        let mut sorted = vec![InventorySlot::EMPTY; self.slots.len()];
+        let mut index = 0;
+        for (item, mut count) in items {
+            while count > 0 && index < sorted.len() {
+                let stack = count.min(self.stack_max);
+                sorted[index] = InventorySlot { item: Some(item), count: stack };
+                count -= stack;
+                index += 1;
+            }
+        }
+        self.slots = sorted;
+    }
+
     /// Remove one unit from slot_index. Returns the item type if successful.
     /// Caller routes the returned item to Bagira consignment on player discard.
     pub fn discard_one(&mut self, slot_index: usize) -> Option<ItemType> {
@@ -98,4 +230,156 @@ impl Inventory {
         }
         None
     }
+
+    /// Move as much of the stack in `index` as `to` has room for. Returns
+    /// how many moved; whatever doesn't fit stays put.
+    pub fn transfer_slot(&mut self, index: usize, to: &mut Inventory) -> u32 {
+        let Some(item) = self.slots.get(index).and_then(|slot| slot.item) else {
+            return 0;
+        };
+        let mut moved = 0;
+        while self.slots[index].item.is_some() && to.push(item) {
+            self.discard_one(index);
+            moved += 1;
+        }
+        moved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filled(stacks: &[(ItemType, u32)]) -> Inventory {
+        let mut inventory = Inventory::new();
+        for (index, (item, count)) in stacks.iter().enumerate() {
+            inventory.slots[index] = InventorySlot { item: Some(*item), count: *count };
+        }
+        inventory
+    }
+
+    fn total(inventory: &Inventory, item: ItemType) -> u32 {
+        inventory.slots.iter().filter(|slot| slot.item == Some(item)).map(|slot| slot.count).sum()
+    }
+
+    #[test]
+    fn move_slot_into_empty_moves_the_stack() {
+        let mut inventory = filled(&[(ItemType::SeedDay, 4)]);
+        inventory.move_slot(0, 3);
+        assert!(inventory.slots[0].item.is_none());
+        assert_eq!(inventory.slots[3].item, Some(ItemType::SeedDay));
+        assert_eq!(inventory.slots[3].count, 4);
+    }
+
+    #[test]
+    fn move_slot_merge_stops_at_the_stack_size() {
+        let mut inventory = filled(&[(ItemType::SeedDay, 6), (ItemType::SeedDay, 7)]);
+        inventory.move_slot(0, 1);
+        assert_eq!(inventory.slots[1].count, STACK_MAX);
+        assert_eq!(inventory.slots[0].count, 13 - STACK_MAX);
+        assert_eq!(total(&inventory, ItemType::SeedDay), 13);
+    }
+
+    #[test]
+    fn move_slot_merge_that_fits_empties_the_source() {
+        let mut inventory = filled(&[(ItemType::Fertilizer, 2), (ItemType::Fertilizer, 3)]);
+        inventory.move_slot(0, 1);
+        assert!(inventory.slots[0].item.is_none());
+        assert_eq!(inventory.slots[0].count, 0);
+        assert_eq!(inventory.slots[1].count, 5);
+    }
+
+    #[test]
+    fn move_slot_onto_a_different_item_swaps() {
+        let mut inventory = filled(&[(ItemType::SeedDay, 2), (ItemType::WateringCan, 5)]);
+        inventory.move_slot(0, 1);
+        assert_eq!(inventory.slots[0].item, Some(ItemType::WateringCan));
+        assert_eq!(inventory.slots[0].count, 5);
+        assert_eq!(inventory.slots[1].item, Some(ItemType::SeedDay));
+        assert_eq!(inventory.slots[1].count, 2);
+    }
+
+    #[test]
+    fn move_slot_out_of_range_does_nothing() {
+        let mut inventory = filled(&[(ItemType::SeedDay, 2)]);
+        inventory.move_slot(0, INVENTORY_SLOTS);
+        assert_eq!(inventory.slots[0].count, 2);
+    }
+
+    #[test]
+    fn split_moves_half_rounded_down() {
+        let mut inventory = filled(&[(ItemType::SeedNight, 7)]);
+        assert!(inventory.split(0));
+        assert_eq!(inventory.slots[0].count, 4);
+        assert_eq!(inventory.slots[1].item, Some(ItemType::SeedNight));
+        assert_eq!(inventory.slots[1].count, 3);
+    }
+
+    #[test]
+    fn split_refuses_a_single_item_or_a_full_satchel() {
+        let mut single = filled(&[(ItemType::SeedNight, 1)]);
+        assert!(!single.split(0));
+        assert_eq!(single.slots[0].count, 1);
+
+        let mut full = filled(&[(ItemType::SeedDay, 4); INVENTORY_SLOTS]);
+        assert!(!full.split(0));
+        assert!(full.slots.iter().all(|slot| slot.count == 4));
+
+        assert!(!Inventory::new().split(0));
+    }
+
+    #[test]
+    fn sort_merges_and_keeps_every_item() {
+        let mut inventory = filled(&[
+            (ItemType::FoodBuff(FoodBuffKind::MintLeaf), 1),
+            (ItemType::SeedDay, 7),
+            (ItemType::WateringCan, 2),
+            (ItemType::SeedDay, 9),
+            (ItemType::WateringCan, 3),
+        ]);
+        inventory.sort();
+        assert_eq!(total(&inventory, ItemType::SeedDay), 16);
+        assert_eq!(total(&inventory, ItemType::WateringCan), 5);
+        assert_eq!(total(&inventory, ItemType::FoodBuff(FoodBuffKind::MintLeaf)), 1);
+        assert!(inventory.slots.iter().all(|slot| slot.count <= STACK_MAX));
+        // Garden goods first, food last, empty slots at the end.
+        assert_eq!(inventory.slots[0].item, Some(ItemType::WateringCan));
+        assert_eq!(inventory.slots[0].count, 5);
+        assert_eq!(inventory.slots[3].item, Some(ItemType::FoodBuff(FoodBuffKind::MintLeaf)));
+        assert!(inventory.slots[4..].iter().all(|slot| slot.item.is_none()));
+    }
+
+    #[test]
+    fn sort_repacks_a_full_satchel_without_losing_items() {
+        let mut inventory = filled(&[(ItemType::SeedDay, 5); INVENTORY_SLOTS]);
+        inventory.sort();
+        assert_eq!(total(&inventory, ItemType::SeedDay), 5 * INVENTORY_SLOTS as u32);
+        assert_eq!(inventory.slots.iter().filter(|slot| slot.item.is_some()).count(), INVENTORY_SLOTS / 2);
+    }
+
+    #[test]
+    fn transfer_slot_moves_what_fits_and_leaves_the_rest() {
+        let mut bag = filled(&[(ItemType::Fertilizer, 8)]);
+        let mut shed = Inventory::with_capacity(1, STACK_MAX);
+        shed.slots[0] = InventorySlot { item: Some(ItemType::Fertilizer), count: 5 };
+        assert_eq!(bag.transfer_slot(0, &mut shed), 5);
+        assert_eq!(bag.slots[0].count, 3);
+        assert_eq!(shed.slots[0].count, STACK_MAX);
+        assert_eq!(bag.transfer_slot(0, &mut shed), 0);
+        assert_eq!(Inventory::new().transfer_slot(0, &mut shed), 0);
+    }
+
+    #[test]
+    fn set_capacity_refuses_to_drop_carried_items() {
+        let mut inventory = filled(&[(ItemType::SeedDay, 2)]);
+        inventory.raise_capacity(12, 20);
+        inventory.slots[10] = InventorySlot { item: Some(ItemType::SeedNight), count: 15 };
+        assert!(!inventory.set_capacity(8, 20));
+        assert!(!inventory.set_capacity(12, 10));
+        assert_eq!((inventory.slot_count(), inventory.stack_max()), (12, 20));
+
+        inventory.slots[10] = InventorySlot::EMPTY;
+        assert!(inventory.set_capacity(8, 10));
+        assert_eq!((inventory.slot_count(), inventory.stack_max()), (8, 10));
+    }
 }
@@ -0,0 +1,104 @@
+// --- Inventory screen ---
+//
+// The whole satchel laid out to rearrange, opened with Tab from the board or
+// the garden. Drag a stack onto an empty slot to move it, onto the same item
+// to merge, or onto anything else to swap; shift-click splits a stack in two.
+// The HUD chips and the garden drawer follow the order set here.
+
+use macroquad::prelude::*;
+
+use crate::game_state::{GamePhase, GameState};
+use crate::render;
+use crate::replay::ReplayAction;
+use crate::ui_layout::{inventory_screen_slot_rect, inventory_sort_button_rect, point_in_rect};
+
+impl GameState {
+    /// On the board the satchel only changes while nothing is moving, so
+    /// drops always stack the same way on replay.
+    pub(crate) fn can_edit_inventory(&self) -> bool {
+        self.phase == GamePhase::Garden || self.board_idle()
+    }
+
+    pub(crate) fn toggle_inventory_screen(&mut self) {
+        self.inventory_open = !self.inventory_open;
+        self.inventory_drag = None;
+    }
+
+    fn inventory_slot_at(&self, mx: f32, my: f32) -> Option<usize> {
+        let slot_count = self.inventory.slot_count();
+        (0..slot_count).find(|index| {
+            let (x, y, w, h) = inventory_screen_slot_rect(*index, slot_count);
+            point_in_rect(mx, my, x, y, w, h)
+        })
+    }
+
+    pub(crate) fn update_inventory_screen(&mut self) {
+        if is_key_pressed(KeyCode::Tab) || is_key_pressed(KeyCode::Escape) {
+            self.toggle_inventory_screen();
+            return;
+        }
+        // Read-only while the board moves, so nothing is recorded that the
+        // live game refused.
+        if !self.can_edit_inventory() {
+            self.inventory_drag = None;
+            return;
+        }
+
+        let (mx, my) = mouse_position();
+        let hovered = self.inventory_slot_at(mx, my);
+        if is_mouse_button_pressed(MouseButton::Right) {
+            if let Some(slot) = hovered {
+                self.request_discard(slot);
+            }
+            return;
+        }
+
+        if is_mouse_button_pressed(MouseButton::Left) {
+            let (sort_x, sort_y, sort_w, sort_h) = inventory_sort_button_rect();
+            if point_in_rect(mx, my, sort_x, sort_y, sort_w, sort_h) {
+                self.perform_action(ReplayAction::InventorySort);
+                return;
+            }
+            let Some(slot) = hovered.filter(|slot| self.inventory.slots[*slot].item.is_some()) else {
+                return;
+            };
+            if is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift) {
+                self.perform_action(ReplayAction::InventorySplit { slot });
+            } else {
+                self.inventory_drag = Some(slot);
+            }
+        } else if !is_mouse_button_down(MouseButton::Left) {
+            if let Some(from) = self.inventory_drag.take() {
+                if let Some(to) = hovered.filter(|to| *to != from) {
+                    self.perform_action(ReplayAction::InventoryMove { from, to });
+                }
+            }
+        }
+    }
+
+    pub(crate) fn move_inventory_slot(&mut self, from: usize, to: usize) {
+        if self.can_edit_inventory() {
+            self.inventory.move_slot(from, to);
+            self.drop_undo();
+        }
+    }
+
+    pub(crate) fn split_inventory_slot(&mut self, slot: usize) {
+        if self.can_edit_inventory() && self.inventory.split(slot) {
+            self.drop_undo();
+        }
+    }
+
+    pub(crate) fn sort_inventory(&mut self) {
+        if self.can_edit_inventory() {
+            self.inventory.sort();
+            self.drop_undo();
+        }
+    }
+
+    pub(crate) fn draw_inventory_screen(&self) {
+        if self.inventory_open {
+            render::draw_inventory_screen(&self.inventory, self.inventory_drag, self.can_edit_inventory());
+        }
+    }
+}
@@ -0,0 +1,195 @@
+// --- Move-limited and timed levels ---
+//
+// A level can cap its moves or its time. Running out before the level is won
+// sends the game to LevelFailed, where the player retries on a fresh board.
+// So does a board that no reshuffle or reroll could make playable.
+// The score, spending and illicit cost go back to where the level started;
+// inventory and anything else earned along the way is kept. Winning with moves or time to spare
+// turns what's left into a finale of line blasts, and their points are the
+// bonus.
+
+use crate::game_state::{GamePhase, GameState};
+use crate::scoring::ScoreBreakdown;
+use crate::tile::SpecialGem;
+
+pub const FINALE_SECONDS_PER_CHARGE: f32 = 5.0; // spare time per finale blast
+pub const FINALE_MAX_CHARGES: u32 = 15;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FailReason {
+    OutOfMoves,
+    OutOfTime,
+    ObjectiveMissed,
+    BoardStuck, // no playable board could be dealt
+}
+
+impl FailReason {
+    pub fn message(self) -> &'static str {
+        match self {
+            FailReason::OutOfMoves => "OUT OF MOVES",
+            FailReason::OutOfTime => "OUT OF TIME",
+            FailReason::ObjectiveMissed => "OBJECTIVE MISSED",
+            FailReason::BoardStuck => "NO MOVES LEFT",
+        }
+    }
+}
+
+impl GameState {
+    /// Reset the per-level counters and objectives. Call whenever a level
+    /// starts, including retries.
+    pub(crate) fn begin_level(&mut self) {
+        self.moves_made = 0;
+        self.level_start_points = self.total_points;
+        self.level_start_spent = self.spent_points;
+        self.level_start_illicit = self.illegal_move_cost;
+        self.time_left = self.level_def(self.level).and_then(|def| def.time_limit);
+        self.finale_charges = 0;
+        self.fail_reason = None;
+        self.undo = None;
+        self.undos_used = 0;
+        self.armed = None;
+        self.discard_confirm = None;
+        self.expire_modifiers();
+        self.expire_buffs();
+        self.apply_spawn_table();
+        self.begin_level_objectives();
+    }
+
+    pub(crate) fn moves_left(&self) -> Option<u32> {
+        self.level_def(self.level)
+            .and_then(|def| def.move_limit)
+            .map(|limit| limit.saturating_sub(self.moves_made))
+    }
+
+    /// Strict rules send a swap that makes no match back where it came from,
+    /// free of charge, instead of keeping it as an illicit move.
+    pub(crate) fn strict_swaps(&self) -> bool {
+        self.settings.strict_swaps || self.level_forces_strict()
+    }
+
+    /// A `strict` level keeps the rule on whatever the setting says.
+    pub(crate) fn level_forces_strict(&self) -> bool {
+        self.level_def(self.level).is_some_and(|def| def.strict)
+    }
+
+    pub(crate) fn in_finale(&self) -> bool {
+        self.finale_charges > 0
+    }
+
+    /// Count down a timed level. The clock stops for farming and the finale.
+    pub(crate) fn tick_level_clock(&mut self, delta: f32) {
+        if self.is_farming || self.in_finale() {
+            return;
+        }
+        if let Some(time_left) = self.time_left.as_mut() {
+            *time_left = (*time_left - delta).max(0.0);
+        }
+    }
+
+    fn level_won(&self) -> bool {
+        self.total_points >= self.target && self.objectives_complete()
+    }
+
+    fn spare_finale_charges(&self) -> u32 {
+        let from_moves = self.moves_left().unwrap_or(0);
+        let from_time = self.time_left.map_or(0, |time| (time / FINALE_SECONDS_PER_CHARGE).ceil() as u32);
+        (from_moves + from_time).min(FINALE_MAX_CHARGES)
+    }
+
+    /// Decide whether the level is won, lost or still going. Only call with an
+    /// idle board, so a cascade in flight can still win it.
+    pub(crate) fn check_level_end(&mut self) {
+        if self.in_finale() {
+            return;
+        }
+
+        if self.level_won() {
+            // The winning move stands.
+            self.undo = None;
+            let spare = self.spare_finale_charges();
+            if spare == 0 {
+                self.phase = GamePhase::LevelTransition;
+                return;
+            }
+            // Spend the leftovers so the finale only runs once.
+            self.finale_charges = spare;
+            self.move_score = ScoreBreakdown::default();
+            if let Some(limit) = self.level_def(self.level).and_then(|def| def.move_limit) {
+                self.moves_made = limit;
+            }
+            if self.time_left.is_some() {
+                self.time_left = Some(0.0);
+            }
+            self.selected = None;
+            self.hint = None;
+            return;
+        }
+
+        let reason = if self.moves_left() == Some(0) {
+            Some(FailReason::OutOfMoves)
+        } else if self.time_left == Some(0.0) {
+            Some(FailReason::OutOfTime)
+        } else if self.objective_failed() {
+            Some(FailReason::ObjectiveMissed)
+        } else if self.board_stuck {
+            Some(FailReason::BoardStuck)
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            self.fail_reason = Some(reason);
+            self.undo = None;
+            self.selected = None;
+            self.phase = GamePhase::LevelFailed;
+        }
+    }
+
+    /// Turn one finale charge into a line blast on a random gem.
+    pub(crate) fn fire_finale_charge(&mut self) {
+        self.finale_charges -= 1;
+        self.chain = 0;
+        let cells: Vec<(usize, usize)> = self
+            .grid
+            .cells()
+            .into_iter()
+            .filter(|(x, y)| self.grid[*x][*y].kind.is_gem() && self.grid[*x][*y].special.is_none())
+            .collect();
+        if cells.is_empty() {
+            return;
+        }
+        let (x, y) = cells[self.rng.board.gen_range(0, cells.len())];
+        let gem = if self.rng.board.gen_range(0, 2) == 0 {
+            SpecialGem::LineHorizontal
+        } else {
+            SpecialGem::LineVertical
+        };
+        self.grid[x][y].special = Some(gem);
+        self.begin_match_clear(vec![], vec![(x, y, self.grid[x][y].kind)], true);
+    }
+
+    /// Start the level over on a fresh board, with the score, spending and
+    /// illicit cost it started with. Illicit charges and undo fees paid in
+    /// the failed attempt are refunded along with the points they came from.
+    pub(crate) fn retry_level(&mut self) {
+        self.total_points = self.level_start_points;
+        self.spent_points = self.level_start_spent;
+        self.illegal_move_cost = self.level_start_illicit;
+        self.reset_board();
+        self.begin_level();
+        self.phase = GamePhase::Playing;
+    }
+
+    /// HUD readout for a limited level, and whether it's running low.
+    pub(crate) fn level_limit_readout(&self) -> Option<(String, bool)> {
+        if self.in_finale() {
+            return Some((format!("Bonus x{}", self.finale_charges), false));
+        }
+        if let Some(moves) = self.moves_left() {
+            return Some((format!("Moves: {}", moves), moves <= 3));
+        }
+        self.time_left.map(|time| {
+            let seconds = time.ceil() as u32;
+            (format!("Time: {}:{:02}", seconds / 60, seconds % 60), time <= 10.0)
+        })
+    }
+}
@@ -0,0 +1,397 @@
+// --- Level definitions ---
+//
+// Levels can be described in a plain-text file instead of code; see
+// docs/LEVEL_FORMAT.md for the full format. Each `level` line starts a new
+// level and the keys after it fill it in. Anything left out keeps the default
+// that the built-in progression uses.
+
+use crate::board::{BoardShape, SHAPE_PRESETS};
+use crate::game_state::{GameState, GRID_HEIGHT, GRID_WIDTH, LEVELS_PER_SET, LEVEL_TARGET_STEP};
+use crate::objectives::LevelObjective;
+use crate::spawn::{self, SpawnTable, SpawnWeights};
+use crate::tile::TileType;
+
+pub const LEVELS_PATH: &str = "assets/levels.txt";
+
+#[derive(Clone, Debug)]
+pub struct LevelDef {
+    pub target_step: i32, // points added to the cumulative target
+    pub biome: usize,
+    pub shape: BoardShape,
+    pub spawn_weights: Option<SpawnWeights>, // None keeps the biome's weights
+    pub objectives: Vec<LevelObjective>,
+    pub move_limit: Option<u32>,
+    pub time_limit: Option<f32>, // seconds
+    pub blockers: Vec<(usize, usize)>,
+    pub strict: bool, // swaps that make no match always slide back, whatever the setting
+}
+
+impl LevelDef {
+    fn new(biome: usize) -> Self {
+        Self {
+            target_step: LEVEL_TARGET_STEP,
+            biome,
+            shape: BoardShape::rect(GRID_WIDTH, GRID_HEIGHT),
+            spawn_weights: None,
+            objectives: vec![],
+            move_limit: None,
+            time_limit: None,
+            blockers: vec![],
+            strict: false,
+        }
+    }
+}
+
+fn parse_kind(word: &str) -> Option<TileType> {
+    match word {
+        "sun" => Some(TileType::Sun),
+        "moon" => Some(TileType::Moon),
+        "water" => Some(TileType::Water),
+        "leaf" => Some(TileType::Leaf),
+        "exotic" => Some(TileType::Exotic),
+        _ => None,
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(word: Option<&&str>, what: &str) -> Result<T, String> {
+    word.and_then(|word| word.parse::<T>().ok())
+        .ok_or_else(|| format!("expected {}", what))
+}
+
+/// A number that has to be above zero, like a move limit or a tile count.
+fn parse_positive<T: std::str::FromStr + PartialOrd + Default>(word: Option<&&str>, what: &str) -> Result<T, String> {
+    let number: T = parse_number(word, what)?;
+    if number > T::default() {
+        Ok(number)
+    } else {
+        Err(format!("expected {} above 0", what))
+    }
+}
+
+fn parse_objective(words: &[&str]) -> Result<LevelObjective, String> {
+    match words.first().copied() {
+        Some("collect") => {
+            let kind = words
+                .get(1)
+                .and_then(|word| parse_kind(word))
+                .ok_or("expected a tile kind (sun, moon, water, leaf, exotic)")?;
+            let count = parse_positive(words.get(2), "a tile count")?;
+            Ok(LevelObjective::CollectTiles { kind, count })
+        }
+        Some("blockers") => Ok(LevelObjective::ClearBlockers),
+        Some("ingredients") => Ok(LevelObjective::BringIngredients { count: parse_positive(words.get(1), "an ingredient count")? }),
+        Some("score") => Ok(LevelObjective::ScoreWithinMoves {
+            points: parse_positive(words.get(1), "a point total")?,
+            moves: parse_positive(words.get(2), "a move count")?,
+        }),
+        _ => Err("unknown objective (collect, blockers, ingredients, score)".to_owned()),
+    }
+}
+
+/// A level still being read, with its board lines kept until the end.
+struct LevelDraft {
+    line: usize, // where its `level` line is, for errors
+    level: LevelDef,
+    size: Option<(usize, usize)>,
+    mask: Vec<String>,
+    preset: Option<BoardShape>,
+}
+
+/// Whether a swap could ever make a match: somewhere three free cells in a
+/// line, plus a fourth free cell next to them to swap the last tile in from.
+/// Without one the board can never be made playable.
+fn allows_a_swap(shape: &BoardShape, blockers: &[(usize, usize)]) -> bool {
+    let free = |x: usize, y: usize| x < shape.width && y < shape.height && shape.mask[x][y] && !blockers.contains(&(x, y));
+    let neighbours = |(x, y): (usize, usize)| {
+        [(x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1)]
+    };
+    (0..shape.width).flat_map(|x| (0..shape.height).map(move |y| (x, y))).any(|(x, y)| {
+        [(1, 0), (0, 1)].iter().any(|(dx, dy)| {
+            let line = [(x, y), (x + dx, y + dy), (x + dx * 2, y + dy * 2)];
+            line.iter().all(|(lx, ly)| free(*lx, *ly))
+                && line
+                    .iter()
+                    .flat_map(|cell| neighbours(*cell))
+                    .any(|(nx, ny)| free(nx, ny) && !line.contains(&(nx, ny)))
+        })
+    })
+}
+
+/// Finish a level once all its lines are read: apply the mask and check the
+/// blockers sit on playable cells and leave room to play.
+fn finish_level(draft: LevelDraft) -> Result<LevelDef, String> {
+    let LevelDraft { mut level, size, mask, preset, .. } = draft;
+    if let Some(preset) = preset {
+        if size.is_some() || !mask.is_empty() {
+            return Err("shape can't be combined with size or mask".to_owned());
+        }
+        level.shape = preset;
+    } else if !mask.is_empty() {
+        level.shape = BoardShape::from_rows(&mask).ok_or("mask rows must be the same width and use only # and .")?;
+        if size.is_some_and(|size| size != (level.shape.width, level.shape.height)) {
+            return Err("size does not match the mask".to_owned());
+        }
+    } else if let Some((width, height)) = size {
+        level.shape = BoardShape::rect(width, height);
+    }
+
+    let shape = &level.shape;
+    if let Some((x, y)) = level.blockers.iter().find(|(x, y)| *x >= shape.width || *y >= shape.height || !shape.mask[*x][*y]) {
+        return Err(format!("blocker {} {} is not on a playable cell", x, y));
+    }
+    if !allows_a_swap(shape, &level.blockers) {
+        return Err("board needs three free cells in a line and one more beside them to allow a swap".to_owned());
+    }
+    Ok(level)
+}
+
+fn finish_draft(draft: LevelDraft) -> Result<LevelDef, String> {
+    let line = draft.line;
+    finish_level(draft).map_err(|message| format!("level on line {}: {}", line, message))
+}
+
+/// Parse a level file. Errors name the offending line.
+pub fn parse_levels(data: &str) -> Result<Vec<LevelDef>, String> {
+    let mut levels = vec![];
+    let mut current: Option<LevelDraft> = None;
+
+    for (index, raw) in data.lines().enumerate() {
+        // Comments take a whole line; '#' also draws mask cells.
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let fail = |message: String| format!("line {}: {}", index + 1, message);
+
+        if words[0] == "level" {
+            if let Some(draft) = current.take() {
+                levels.push(finish_draft(draft)?);
+            }
+            // A new level stays in the previous level's biome unless told otherwise.
+            let biome = levels.last().map(|level: &LevelDef| level.biome).unwrap_or(0);
+            current = Some(LevelDraft { line: index + 1, level: LevelDef::new(biome), size: None, mask: vec![], preset: None });
+            continue;
+        }
+
+        let Some(LevelDraft { level, size, mask, preset, .. }) = current.as_mut() else {
+            return Err(fail(format!("'{}' before the first 'level' line", words[0])));
+        };
+        let result: Result<(), String> = match words[0] {
+            "target" => parse_number(words.get(1), "a point step").map(|step| level.target_step = step),
+            "biome" => parse_number(words.get(1), "a biome index").map(|biome| level.biome = biome),
+            "size" => parse_number(words.get(1), "a width").and_then(|width: usize| {
+                let height: usize = parse_number(words.get(2), "a height")?;
+                if width == 0 || height == 0 {
+                    return Err("board size must be at least 1x1".to_owned());
+                }
+                *size = Some((width, height));
+                Ok(())
+            }),
+            "shape" => words
+                .get(1)
+                .and_then(|name| BoardShape::preset(name))
+                .map(|shape| *preset = Some(shape))
+                .ok_or_else(|| format!("expected a board shape ({})", SHAPE_PRESETS.join(", "))),
+            "mask" => match words.get(1) {
+                Some(row) => {
+                    mask.push((*row).to_owned());
+                    Ok(())
+                }
+                None => Err("expected a row of # and .".to_owned()),
+            },
+            "weights" => words[1..]
+                .iter()
+                .map(|word| word.parse::<u32>().ok())
+                .collect::<Option<Vec<u32>>>()
+                .and_then(|weights| weights.try_into().ok())
+                .ok_or_else(|| "expected five weights: sun moon water leaf exotic".to_owned())
+                .and_then(|weights: SpawnWeights| {
+                    // One colour (Exotics match anything) would match everywhere, forever.
+                    if weights[..4].iter().filter(|weight| **weight > 0).count() < 2 {
+                        return Err("at least two of sun, moon, water and leaf need a weight above 0".to_owned());
+                    }
+                    level.spawn_weights = Some(weights);
+                    Ok(())
+                }),
+            "objective" => parse_objective(&words[1..]).map(|objective| level.objectives.push(objective)),
+            "moves" => parse_positive(words.get(1), "a move count").map(|moves| level.move_limit = Some(moves)),
+            "time" => parse_number(words.get(1), "a number of seconds")
+                .and_then(|seconds: f32| if seconds > 0.0 { Ok(seconds) } else { Err("time must be positive".to_owned()) })
+                .map(|seconds| level.time_limit = Some(seconds)),
+            "blocker" => parse_number(words.get(1), "a column").and_then(|x: usize| {
+                let y: usize = parse_number(words.get(2), "a row")?;
+                level.blockers.push((x, y));
+                Ok(())
+            }),
+            "strict" => {
+                level.strict = true;
+                Ok(())
+            }
+            other => Err(format!("unknown key '{}'", other)),
+        };
+        result.map_err(fail)?;
+    }
+
+    if let Some(draft) = current.take() {
+        levels.push(finish_draft(draft)?);
+    }
+    Ok(levels)
+}
+
+/// Read the level file at `path`. A missing file is not an error: it just
+/// means the built-in progression is used.
+pub fn load_levels(path: &str) -> Result<Vec<LevelDef>, String> {
+    match std::fs::read_to_string(path) {
+        Ok(data) => parse_levels(&data),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(vec![]),
+        Err(error) => Err(error.to_string()),
+    }
+}
+
+impl GameState {
+    pub(crate) fn level_def(&self, level: i32) -> Option<&LevelDef> {
+        usize::try_from(level - 1).ok().and_then(|index| self.levels.get(index))
+    }
+
+    /// Biome for a level: from the level file, or a new biome every
+    /// `LEVELS_PER_SET` levels. Past the end of the file the formula carries on
+    /// from the file's last biome.
+    pub(crate) fn biome_for_level(&self, level: i32) -> usize {
+        if let Some(def) = self.level_def(level) {
+            return def.biome;
+        }
+        match self.levels.last() {
+            Some(last) => last.biome + 1 + (level - 1 - self.levels.len() as i32).max(0) as usize / LEVELS_PER_SET as usize,
+            None => ((level - 1) / LEVELS_PER_SET) as usize,
+        }
+    }
+
+    pub(crate) fn biome_index(&self) -> usize {
+        self.biome_for_level(self.level)
+    }
+
+    pub(crate) fn target_step_for_level(&self, level: i32) -> i32 {
+        self.level_def(level).map_or(LEVEL_TARGET_STEP, |def| def.target_step)
+    }
+
+    /// The cumulative score a level asks for: every step up to and including it.
+    pub(crate) fn target_for_level(&self, level: i32) -> i32 {
+        (1..=level).map(|l| self.target_step_for_level(l)).sum()
+    }
+
+    pub(crate) fn board_shape_for_level(&self, level: i32) -> BoardShape {
+        self.level_def(level)
+            .map_or_else(|| BoardShape::rect(GRID_WIDTH, GRID_HEIGHT), |def| def.shape.clone())
+    }
+
+    /// A level's spawn table: its own weights from the level file, or else
+    /// its biome's defaults. No modifiers.
+    pub(crate) fn spawn_table_for_level(&self, level: i32) -> SpawnTable {
+        let weights = self
+            .level_def(level)
+            .and_then(|def| def.spawn_weights)
+            .unwrap_or_else(|| spawn::biome_spawn_weights(self.biome_for_level(level)));
+        SpawnTable::new(weights)
+    }
+
+    /// Point the board at the current level's spawn table, with the shifts
+    /// from any active modifiers on top.
+    pub(crate) fn apply_spawn_table(&mut self) {
+        *self.grid.spawn_table_mut() = self.spawn_table_for_level(self.level);
+        self.apply_modifier_spawn_shifts();
+    }
+
+    pub(crate) fn advance_level(&mut self) {
+        self.level += 1;
+        self.target += self.target_step_for_level(self.level);
+        self.apply_level_board();
+        self.begin_level();
+    }
+
+    /// The shop opens between biomes.
+    pub(crate) fn next_level_changes_biome(&self) -> bool {
+        self.biome_for_level(self.level + 1) != self.biome_index()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(data: &str) -> String {
+        parse_levels(data).expect_err("level file should be rejected")
+    }
+
+    #[test]
+    fn parses_a_full_level() {
+        let data = "# comment\nlevel\nbiome 2\ntarget 1500\nsize 7 6\nweights 3 3 0 2 1\nobjective collect sun 20\nmoves 25\ntime 90\nblocker 3 0\nstrict\nlevel\nshape heart\n";
+        let levels = parse_levels(data).expect("level file should parse");
+        assert_eq!(levels.len(), 2);
+        let first = &levels[0];
+        assert_eq!((first.biome, first.target_step), (2, 1500));
+        assert_eq!((first.shape.width, first.shape.height), (7, 6));
+        assert_eq!(first.spawn_weights, Some([3, 3, 0, 2, 1]));
+        assert_eq!(first.objectives.len(), 1);
+        assert_eq!((first.move_limit, first.time_limit), (Some(25), Some(90.0)));
+        assert_eq!(first.blockers, vec![(3, 0)]);
+        assert!(first.strict);
+        // The next level keeps the biome and picks up the preset.
+        assert_eq!(levels[1].biome, 2);
+        assert_eq!(Some(levels[1].shape.clone()), BoardShape::preset("heart"));
+    }
+
+    #[test]
+    fn mask_sets_the_board_size() {
+        let levels = parse_levels("level\nmask .###.\nmask #####\nmask .###.\n").expect("mask should parse");
+        assert_eq!((levels[0].shape.width, levels[0].shape.height), (5, 3));
+        assert!(!levels[0].shape.mask[0][0]);
+    }
+
+    #[test]
+    fn an_empty_file_has_no_levels() {
+        assert!(parse_levels("").expect("empty file should parse").is_empty());
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        assert!(error("target 100\n").starts_with("line 1:"));
+        assert!(error("level\n\nfoo 1\n").starts_with("line 3: unknown key"));
+        assert!(error("level\nsize 0 4\n").starts_with("line 2:"));
+        assert!(error("level\nweights 1 2 3\n").starts_with("line 2:"));
+        assert!(error("level\nobjective dance\n").starts_with("line 2:"));
+        assert!(error("level\ntime -5\n").starts_with("line 2:"));
+        assert!(error("level\nmoves 0\n").contains("above 0"));
+        assert!(error("level\nobjective collect sun 0\n").contains("above 0"));
+        assert!(error("level\nobjective ingredients 0\n").contains("above 0"));
+        assert!(error("level\nobjective score 500 0\n").contains("above 0"));
+        assert!(error("level\nshape star\n").starts_with("line 2:"));
+    }
+
+    #[test]
+    fn rejects_weights_with_one_colour() {
+        assert!(error("level\nweights 5 0 0 0 3\n").contains("two of sun"));
+        assert!(error("level\nweights 0 0 0 0 1\n").contains("two of sun"));
+    }
+
+    #[test]
+    fn rejects_boards_that_cannot_hold_a_swap() {
+        assert!(error("level\nsize 2 2\n").starts_with("level on line 1:"));
+        assert!(error("level\nsize 3 1\n").contains("allow a swap"));
+        assert!(error("level\nsize 3 3\nblocker 0 1\nblocker 1 1\nblocker 2 1\nblocker 1 0\nblocker 1 2\n").contains("allow a swap"));
+        // A checkerboard of holes never lines up three cells.
+        assert!(error("level\nmask #.#.\nmask .#.#\nmask #.#.\n").contains("allow a swap"));
+        // Three in a line plus one beside them is enough.
+        assert!(parse_levels("level\nsize 4 1\n").is_ok());
+        assert!(parse_levels("level\nmask ###\nmask #..\n").is_ok());
+    }
+
+    #[test]
+    fn rejects_misplaced_blockers_and_clashing_board_keys() {
+        assert!(error("level\nsize 4 4\nblocker 4 0\n").contains("blocker 4 0"));
+        assert!(error("level\nmask #.##\nmask ####\nblocker 1 0\n").contains("blocker 1 0"));
+        assert!(error("level\nsize 5 5\nmask ####\nmask ####\n").contains("size does not match"));
+        assert!(error("level\nmask ##\nmask ###\n").contains("same width"));
+        assert!(error("level\nshape ring\nsize 8 8\n").contains("can't be combined"));
+    }
+}
@@ -1,13 +1,33 @@
 use macroquad::prelude::*;
 
 mod tile;
+mod board;
 mod game_state;
 mod inventory;
 mod garden;
+mod levels;
+mod spawn;
+mod level_rules;
+mod objectives;
 mod shop;
 mod ui_layout;
 mod economy;
 mod match_logic;
+mod scoring;
+mod popups;
+mod board_moves;
+mod board_input;
+mod undo;
+mod modifiers;
+mod buffs;
+mod targeting;
+mod discard;
+mod inventory_screen;
+mod upgrades;
+mod shed;
+mod settings;
+mod rng;
+mod replay;
 mod render;
 mod phase_update;
 mod gameplay_update;
@@ -23,9 +43,30 @@ fn window_conf() -> Conf {
     }
 }
 
+/// Value following `flag` on the command line, e.g. `--seed 42`.
+fn arg_value(flag: &str) -> Option<String> {
+    let args: Vec<String> = std::env::args().collect();
+    args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|index| args.get(index + 1))
+        .cloned()
+}
+
 #[macroquad::main(window_conf)]
 async fn main() {
-    rand::srand(macroquad::miniquad::date::now() as u64);
+    // `--replay FILE` plays a recorded session back; `--record FILE` writes one.
+    // `--seed N` pins the whole game's randomness; otherwise seed from the clock.
+    let replay = arg_value("--replay").and_then(|path| {
+        replay::load_replay(&path)
+            .map_err(|error| eprintln!("Could not read replay file {}: {}", path, error))
+            .ok()
+    });
+    let seed = replay
+        .as_ref()
+        .map(|(header, _)| header.seed)
+        .or_else(|| arg_value("--seed").and_then(|value| value.parse::<u64>().ok()))
+        .unwrap_or_else(|| (macroquad::miniquad::date::now() * 1000.0) as u64);
+    eprintln!("Lucid Leaves seed: {} (replay with --seed {})", seed, seed);
 
     // Helper: load a texture, falling back to a default if the file doesn't exist
     async fn load_or_fallback(path: &str, fallback: &str) -> Texture2D {
@@ -126,10 +167,20 @@ async fn main() {
     leaves_main.set_filter(FilterMode::Nearest);
     let leaves_aux = load_optional_texture("assets/forest_leaves_aux.png").await;
 
+    // `--levels FILE` picks a level file; without one the game looks for
+    // assets/levels.txt and otherwise uses the built-in progression.
+    let levels_path = arg_value("--levels").unwrap_or_else(|| levels::LEVELS_PATH.to_owned());
+    let levels = levels::load_levels(&levels_path).unwrap_or_else(|error| {
+        eprintln!("Could not load levels from {}: {}", levels_path, error);
+        vec![]
+    });
+
     // 2. PASS IT TO THE GAME
     let mut game = GameState::new(
         biome_sets,
         garden_bg_texture,
+        seed,
+        levels,
     );
     game.leaves_main_texture = Some(leaves_main);
     game.leaves_aux_texture = leaves_aux;
@@ -137,6 +188,14 @@ async fn main() {
     game.cave_tree_texture  = load_optional_texture("assets/cave_overlay_tree.png").await;
     game.cave_nast_texture  = load_optional_texture("assets/cave_overlay_nast.png").await;
 
+    if let Some((header, player)) = replay {
+        game.start_replay(header, player);
+    } else if let Some(path) = arg_value("--record") {
+        if let Err(err) = game.start_recording(&path) {
+            eprintln!("Could not start recording to {}: {}", path, err);
+        }
+    }
+
     loop {
         game.update();
         game.draw();
@@ -1,10 +1,14 @@
 use macroquad::prelude::*;
-use macroquad::rand::gen_range;
 
-use crate::tile::{Tile, TileType};
+use crate::board::Board;
+use crate::rng::RngStream;
+use crate::tile::{SpecialGem, TileType};
 use crate::ui_layout::Layout;
 
 pub type MatchCell = (usize, usize, TileType);
+pub type SpecialSpawn = (usize, usize, TileType, SpecialGem);
+
+pub const MATCH_POINTS_PER_TILE: i32 = 10;
 
 #[derive(Clone, Copy)]
 pub struct GemParticle {
@@ -25,6 +29,7 @@ pub fn tile_particle_color(kind: TileType) -> Color {
         TileType::Water => color_u8!(90, 170, 255, 255),
         TileType::Leaf => color_u8!(150, 235, 150, 255),
         TileType::Exotic => color_u8!(230, 140, 255, 255),
+        TileType::Ingredient => color_u8!(190, 140, 80, 255),
         TileType::Empty => WHITE,
     }
 }
@@ -36,68 +41,396 @@ pub fn pending_match_kind_at(pending_matches: &[MatchCell], x: usize, y: usize)
         .map(|(_, _, kind)| *kind)
 }
 
-pub fn find_matches<const W: usize, const H: usize>(grid: &[[Tile; H]; W]) -> Vec<MatchCell> {
-    let mut to_remove = vec![];
+/// Which axes the runs of a match group lie on.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MatchOrientation {
+    Horizontal,
+    Vertical,
+    Both, // L, T and + shapes
+}
+
+/// Outline of a match group, read from how its runs cross.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MatchShape {
+    Line,
+    L,     // runs meet at their ends
+    T,     // one run ends in the middle of the other
+    Cross, // runs meet in both middles
+}
+
+/// One connected set of matched runs of the same kind. `kind` is the colour
+/// the group matched as, so Exotic cells inside it may report another kind.
+#[derive(Clone, Debug)]
+pub struct MatchGroup {
+    pub cells: Vec<(usize, usize)>,
+    pub kind: TileType,
+    pub orientation: MatchOrientation,
+    pub shape: MatchShape,
+    pub length: usize, // longest straight run in the group
+    pub intersection: Option<(usize, usize)>,
+}
+
+impl MatchGroup {
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        self.cells.contains(&(x, y))
+    }
+}
+
+/// A maximal straight line of 3+ tiles of one colour (Exotics included).
+struct Run {
+    cells: Vec<(usize, usize)>,
+    kind: TileType,
+    horizontal: bool,
+}
+
+/// Maximal runs along one line of cells. Exotic tiles stand in for any
+/// colour, so a segment of `colour`/Exotic tiles with at least one `colour`
+/// is a run of that colour; all-Exotic runs only count when no coloured run
+/// already covers them.
+fn runs_in_line(line: &[((usize, usize), TileType)], horizontal: bool, runs: &mut Vec<Run>) {
+    let mut colored: Vec<Run> = vec![];
+    for color in TileType::COLORS {
+        let mut start = 0;
+        while start < line.len() {
+            let fits = |kind: TileType| kind == color || kind == TileType::Exotic;
+            if !fits(line[start].1) {
+                start += 1;
+                continue;
+            }
+            let mut end = start;
+            while end < line.len() && fits(line[end].1) {
+                end += 1;
+            }
+            let segment = &line[start..end];
+            if segment.len() >= 3 && segment.iter().any(|(_, kind)| *kind == color) {
+                colored.push(Run { cells: segment.iter().map(|(cell, _)| *cell).collect(), kind: color, horizontal });
+            }
+            start = end;
+        }
+    }
+
+    let mut start = 0;
+    while start < line.len() {
+        if line[start].1 != TileType::Exotic {
+            start += 1;
+            continue;
+        }
+        let mut end = start;
+        while end < line.len() && line[end].1 == TileType::Exotic {
+            end += 1;
+        }
+        let cells: Vec<(usize, usize)> = line[start..end].iter().map(|(cell, _)| *cell).collect();
+        let covered = colored.iter().any(|run| cells.iter().all(|cell| run.cells.contains(cell)));
+        if cells.len() >= 3 && !covered {
+            runs.push(Run { cells, kind: TileType::Exotic, horizontal });
+        }
+        start = end;
+    }
+
+    runs.extend(colored);
+}
+
+/// Holes hold empty tiles, so runs never reach across them.
+fn find_runs(grid: &Board) -> Vec<Run> {
+    let mut runs = vec![];
+
+    for y in 0..grid.height() {
+        let line: Vec<_> = (0..grid.width()).map(|x| ((x, y), grid[x][y].kind)).collect();
+        runs_in_line(&line, true, &mut runs);
+    }
+
+    for x in 0..grid.width() {
+        let line: Vec<_> = grid[x].iter().enumerate().map(|(y, tile)| ((x, y), tile.kind)).collect();
+        runs_in_line(&line, false, &mut runs);
+    }
+
+    runs
+}
+
+/// Where two perpendicular runs cross, and whether it sits on an end of each.
+fn run_crossing(a: &Run, b: &Run) -> Option<((usize, usize), bool, bool)> {
+    let cell = *a.cells.iter().find(|cell| b.cells.contains(cell))?;
+    let at_end = |run: &Run| run.cells.first() == Some(&cell) || run.cells.last() == Some(&cell);
+    Some((cell, at_end(a), at_end(b)))
+}
+
+fn build_group(runs: &[&Run]) -> MatchGroup {
+    let mut cells: Vec<(usize, usize)> = vec![];
+    for run in runs {
+        for cell in &run.cells {
+            if !cells.contains(cell) {
+                cells.push(*cell);
+            }
+        }
+    }
+
+    let has_h = runs.iter().any(|run| run.horizontal);
+    let has_v = runs.iter().any(|run| !run.horizontal);
+    let orientation = match (has_h, has_v) {
+        (true, true) => MatchOrientation::Both,
+        (true, false) => MatchOrientation::Horizontal,
+        _ => MatchOrientation::Vertical,
+    };
+
+    let mut intersection = None;
+    let mut shape = MatchShape::Line;
+    'outer: for (index, a) in runs.iter().enumerate() {
+        for b in &runs[index + 1..] {
+            if a.horizontal == b.horizontal {
+                continue;
+            }
+            if let Some((cell, end_a, end_b)) = run_crossing(a, b) {
+                intersection = Some(cell);
+                shape = match (end_a, end_b) {
+                    (true, true) => MatchShape::L,
+                    (false, false) => MatchShape::Cross,
+                    _ => MatchShape::T,
+                };
+                break 'outer;
+            }
+        }
+    }
+
+    MatchGroup {
+        cells,
+        kind: runs[0].kind,
+        orientation,
+        shape,
+        length: runs.iter().map(|run| run.cells.len()).max().unwrap_or(0),
+        intersection,
+    }
+}
+
+/// Find every match on the board as connected groups of runs.
+pub fn find_matches(grid: &Board) -> Vec<MatchGroup> {
+    let runs = find_runs(grid);
+
+    // Union runs that share a cell; each connected set becomes one group.
+    let mut group_of: Vec<usize> = (0..runs.len()).collect();
+    for a in 0..runs.len() {
+        for b in (a + 1)..runs.len() {
+            let shares_cell = runs[a].kind == runs[b].kind
+                && runs[a].cells.iter().any(|cell| runs[b].cells.contains(cell));
+            if shares_cell {
+                let (from, to) = (group_of[b], group_of[a]);
+                for id in group_of.iter_mut() {
+                    if *id == from {
+                        *id = to;
+                    }
+                }
+            }
+        }
+    }
 
-    if W == 0 || H == 0 {
-        return to_remove;
+    let mut groups = vec![];
+    for id in 0..runs.len() {
+        let members: Vec<&Run> = runs
+            .iter()
+            .zip(group_of.iter())
+            .filter(|(_, group)| **group == id)
+            .map(|(run, _)| run)
+            .collect();
+        if !members.is_empty() {
+            groups.push(build_group(&members));
+        }
     }
+    groups
+}
 
-    for (y, _) in grid[0].iter().enumerate() {
-        for x in 0..W.saturating_sub(2) {
-            let t1 = grid[x][y].kind;
-            let t2 = grid[x + 1][y].kind;
-            let t3 = grid[x + 2][y].kind;
-            if t1 != TileType::Empty && t1 == t2 && t2 == t3 {
-                to_remove.push((x, y, t1));
-                to_remove.push((x + 1, y, t2));
-                to_remove.push((x + 2, y, t3));
+/// Flatten groups into the unique cells they clear.
+pub fn group_cells(grid: &Board, groups: &[MatchGroup]) -> Vec<MatchCell> {
+    let mut cells: Vec<MatchCell> = vec![];
+    for group in groups {
+        for (x, y) in &group.cells {
+            if !cells.iter().any(|(cx, cy, _)| (cx, cy) == (x, y)) {
+                cells.push((*x, *y, grid[*x][*y].kind));
             }
         }
     }
+    cells.sort_by_key(|(x, y, _)| (*x, *y));
+    cells
+}
+
+/// Base points for one clear: every tile in a group plus every tile caught
+/// by a blast. `scoring::score_clear` builds its bonuses on top of this.
+pub fn score_for_clear(groups: &[MatchGroup], cleared: &[MatchCell]) -> i32 {
+    // A wildcard Exotic can sit in two groups at once; it still clears once.
+    let mut cells: Vec<(usize, usize)> = groups
+        .iter()
+        .flat_map(|group| group.cells.iter().copied())
+        .chain(cleared.iter().map(|(x, y, _)| (*x, *y)))
+        .collect();
+    cells.sort_unstable();
+    cells.dedup();
+    cells.len() as i32 * MATCH_POINTS_PER_TILE
+}
 
-    for (x, _) in grid.iter().enumerate() {
-        for y in 0..H.saturating_sub(2) {
-            let t1 = grid[x][y].kind;
-            let t2 = grid[x][y + 1].kind;
-            let t3 = grid[x][y + 2].kind;
-            if t1 != TileType::Empty && t1 == t2 && t2 == t3 {
-                to_remove.push((x, y, t1));
-                to_remove.push((x, y + 1, t2));
-                to_remove.push((x, y + 2, t3));
+/// Decide which special gems the matched groups should create.
+/// 5-in-a-line beats an L/T crossing, which beats a plain 4-in-a-line.
+/// Gems land on the swapped cell when it is part of the group, otherwise on
+/// the crossing cell or the middle of the run (cascades).
+pub fn find_special_spawns(groups: &[MatchGroup], swap: Option<[(usize, usize); 2]>) -> Vec<SpecialSpawn> {
+    let mut spawns: Vec<SpecialSpawn> = vec![];
+
+    for group in groups {
+        let gem = if group.length >= 5 {
+            SpecialGem::ColorClear
+        } else if group.shape != MatchShape::Line {
+            SpecialGem::Bomb
+        } else if group.length == 4 {
+            match group.orientation {
+                MatchOrientation::Vertical => SpecialGem::LineVertical,
+                _ => SpecialGem::LineHorizontal,
             }
+        } else {
+            continue;
+        };
+
+        let fallback = group.intersection.unwrap_or(group.cells[group.cells.len() / 2]);
+        let (x, y) = swap
+            .and_then(|pair| pair.into_iter().find(|(sx, sy)| group.contains(*sx, *sy)))
+            .unwrap_or(fallback);
+        if !spawns.iter().any(|(sx, sy, _, _)| (*sx, *sy) == (x, y)) {
+            spawns.push((x, y, group.kind, gem));
         }
     }
 
-    to_remove.sort_by_key(|(x, y, _)| (*x, *y));
-    to_remove.dedup_by_key(|(x, y, _)| (*x, *y));
-    to_remove
+    spawns
 }
 
-pub fn spawn_match_particles<const W: usize, const H: usize>(
-    grid: &[[Tile; H]; W],
+/// Cells hit when the special gem at (x, y) fires. ColorClear targets `kind`.
+/// Line blasts carry on past holes; holes themselves are never hit.
+pub fn special_blast_cells(grid: &Board, x: usize, y: usize, gem: SpecialGem, kind: TileType) -> Vec<(usize, usize)> {
+    let mut cells: Vec<(usize, usize)> = match gem {
+        SpecialGem::LineHorizontal => (0..grid.width()).map(|bx| (bx, y)).collect(),
+        SpecialGem::LineVertical => (0..grid.height()).map(|by| (x, by)).collect(),
+        SpecialGem::Bomb => {
+            let mut cells = vec![];
+            for bx in x.saturating_sub(1)..=x + 1 {
+                for by in y.saturating_sub(1)..=y + 1 {
+                    cells.push((bx, by));
+                }
+            }
+            cells
+        }
+        SpecialGem::ColorClear => {
+            let mut cells = vec![(x, y)];
+            cells.extend(grid.cells().into_iter().filter(|(bx, by)| grid[*bx][*by].kind == kind));
+            cells
+        }
+    };
+    cells.retain(|(bx, by)| grid.is_playable(*bx, *by));
+    cells
+}
+
+/// Mushroom-set Exotics burst into spores that also clear their four neighbours.
+pub fn expand_exotic_spores(grid: &Board, cells: Vec<MatchCell>) -> Vec<MatchCell> {
+    let mut cleared = cells.clone();
+    for (x, y, kind) in cells {
+        if kind != TileType::Exotic {
+            continue;
+        }
+        for (nx, ny) in grid.orthogonal_neighbours(x, y) {
+            let hit = grid[nx][ny].kind;
+            if hit.is_gem() && !cleared.iter().any(|(cx, cy, _)| (*cx, *cy) == (nx, ny)) {
+                cleared.push((nx, ny, hit));
+            }
+        }
+    }
+    cleared
+}
+
+/// Grow a clear set by firing every special gem inside it. Gems caught in a
+/// blast fire too, so chains resolve in a single pass.
+pub fn expand_special_activations(grid: &Board, matches: Vec<MatchCell>) -> Vec<MatchCell> {
+    let mut cleared = matches;
+    let mut queue: Vec<(usize, usize)> = cleared
+        .iter()
+        .filter(|(x, y, _)| grid[*x][*y].special.is_some())
+        .map(|(x, y, _)| (*x, *y))
+        .collect();
+    let mut fired: Vec<(usize, usize)> = vec![];
+
+    while let Some((x, y)) = queue.pop() {
+        if fired.contains(&(x, y)) {
+            continue;
+        }
+        fired.push((x, y));
+        let tile = grid[x][y];
+        let Some(gem) = tile.special else {
+            continue;
+        };
+        for (bx, by) in special_blast_cells(grid, x, y, gem, tile.kind) {
+            let hit = grid[bx][by];
+            if !hit.kind.is_gem() || cleared.iter().any(|(cx, cy, _)| (*cx, *cy) == (bx, by)) {
+                continue;
+            }
+            cleared.push((bx, by, hit.kind));
+            if hit.special.is_some() {
+                queue.push((bx, by));
+            }
+        }
+    }
+
+    cleared.sort_by_key(|(x, y, _)| (*x, *y));
+    cleared
+}
+
+fn burst_particles(
     particles: &mut Vec<GemParticle>,
-    matches: &[MatchCell],
+    rng: &mut RngStream,
+    center_x: f32,
+    center_y: f32,
+    color: Color,
+    count: usize,
+) {
+    for _ in 0..count {
+        let life = rng.gen_range_f32(0.18, 0.35);
+        particles.push(GemParticle {
+            x: center_x + rng.gen_range_f32(-8.0, 8.0),
+            y: center_y + rng.gen_range_f32(-8.0, 8.0),
+            vx: rng.gen_range_f32(-18.0, 18.0),
+            vy: rng.gen_range_f32(-34.0, -10.0),
+            life,
+            max_life: life,
+            size: rng.gen_range_f32(3.0, 7.0),
+            color,
+        });
+    }
+}
+
+fn cell_center(grid: &Board, x: usize, y: usize, layout: &Layout) -> (f32, f32) {
+    (
+        layout.grid_offset_x + x as f32 * layout.tile_size + grid[x][y].offset_x + layout.tile_size * 0.5,
+        layout.grid_offset_y + y as f32 * layout.tile_size + grid[x][y].offset_y + layout.tile_size * 0.5,
+    )
+}
+
+/// Bursts for each matched group; longer groups throw more sparks per tile,
+/// and any blast-only cells in `cleared` get a plain burst.
+pub fn spawn_match_particles(
+    grid: &Board,
+    particles: &mut Vec<GemParticle>,
+    groups: &[MatchGroup],
+    cleared: &[MatchCell],
     layout: &Layout,
+    rng: &mut RngStream,
 ) {
-    for (x, y, kind) in matches {
-        let center_x = layout.grid_offset_x + *x as f32 * layout.tile_size + layout.tile_size * 0.5;
-        let center_y =
-            layout.grid_offset_y + *y as f32 * layout.tile_size + grid[*x][*y].offset_y + layout.tile_size * 0.5;
-        let color = tile_particle_color(*kind);
-        for _ in 0..8 {
-            let life = gen_range(0.18, 0.35);
-            particles.push(GemParticle {
-                x: center_x + gen_range(-8.0, 8.0),
-                y: center_y + gen_range(-8.0, 8.0),
-                vx: gen_range(-18.0, 18.0),
-                vy: gen_range(-34.0, -10.0),
-                life,
-                max_life: life,
-                size: gen_range(3.0, 7.0),
-                color,
-            });
+    for group in groups {
+        let color = tile_particle_color(group.kind);
+        let count = 8 + 2 * group.length.saturating_sub(3);
+        for (x, y) in &group.cells {
+            let (center_x, center_y) = cell_center(grid, *x, *y, layout);
+            burst_particles(particles, rng, center_x, center_y, color, count);
+        }
+    }
+
+    for (x, y, kind) in cleared {
+        if groups.iter().any(|group| group.contains(*x, *y)) {
+            continue;
         }
+        let (center_x, center_y) = cell_center(grid, *x, *y, layout);
+        burst_particles(particles, rng, center_x, center_y, tile_particle_color(*kind), 8);
     }
 }
 
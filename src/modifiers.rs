@@ -0,0 +1,204 @@
+// --- Board modifiers ---
+//
+// Bagira's board items, used from the inventory during a level with the
+// number keys. Most stay active for a while, either the rest of the level or
+// the rest of the biome, and show as badges on the HUD. Fog Clear works at
+// once instead: it breaks one bramble. Items aimed at the board are armed and
+// placed through targeting.rs.
+
+use macroquad::prelude::*;
+
+use crate::economy;
+use crate::game_state::GameState;
+use crate::inventory::{BoardModifierKind, ItemType};
+use crate::objectives::{self, LevelObjective};
+use crate::spawn;
+use crate::targeting;
+use crate::tile::TileType;
+
+pub const WEIGHT_SHIFT_PERCENT: u32 = 200;    // Tile Charm doubles one kind's spawn chance
+pub const ILLEGAL_COST_CAP: i32 = 150;        // most an illicit move can cost under Cost Cap
+pub const CASCADE_BONUS_FROM_CHAIN: u32 = 3;  // Cascade Charm pays from the 4th clear of a move
+pub const CASCADE_BONUS_POINTS: i32 = 150;
+
+/// Number-key order for using modifiers: 1 to 4.
+pub const MODIFIER_KINDS: [BoardModifierKind; 4] = [
+    BoardModifierKind::TileWeightShift,
+    BoardModifierKind::IllegalCostCap,
+    BoardModifierKind::CascadeBonus,
+    BoardModifierKind::FogClear,
+];
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ModifierDuration {
+    Level, // until the next level starts; a retry keeps it
+    Biome, // until the biome changes
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ActiveModifier {
+    pub kind: BoardModifierKind,
+    pub duration: ModifierDuration,
+    pub level: i32, // where it was used, to tell when it runs out
+    pub biome: usize,
+    pub target: Option<TileType>, // the kind a Tile Charm favours
+}
+
+/// How long a modifier lasts once used. None for ones that act at once.
+pub fn modifier_duration(kind: BoardModifierKind) -> Option<ModifierDuration> {
+    match kind {
+        BoardModifierKind::TileWeightShift | BoardModifierKind::IllegalCostCap => Some(ModifierDuration::Level),
+        BoardModifierKind::CascadeBonus => Some(ModifierDuration::Biome),
+        BoardModifierKind::FogClear
+        | BoardModifierKind::TileRemove
+        | BoardModifierKind::FreeSwap
+        | BoardModifierKind::RowClear => None,
+    }
+}
+
+/// Short label and colour for a modifier's badge or inventory chip.
+pub fn modifier_badge_style(kind: BoardModifierKind) -> (&'static str, Color) {
+    match kind {
+        BoardModifierKind::TileWeightShift => ("TILE", color_u8!(120, 190, 110, 255)),
+        BoardModifierKind::IllegalCostCap => ("CAP", color_u8!(220, 140, 60, 255)),
+        BoardModifierKind::CascadeBonus => ("CSC", color_u8!(200, 120, 230, 255)),
+        BoardModifierKind::FogClear => ("FOG", color_u8!(150, 170, 190, 255)),
+        BoardModifierKind::TileRemove => ("TROW", color_u8!(160, 120, 80, 255)),
+        BoardModifierKind::FreeSwap => ("SWAP", color_u8!(90, 180, 200, 255)),
+        BoardModifierKind::RowClear => ("ROW", color_u8!(210, 90, 90, 255)),
+    }
+}
+
+/// One HUD badge for an active modifier or food buff.
+#[derive(Clone)]
+pub struct ModifierBadge {
+    pub label: &'static str,
+    pub color: Color,
+    pub detail: String, // what it does and how long it lasts
+}
+
+impl GameState {
+    pub(crate) fn has_modifier(&self, kind: BoardModifierKind) -> bool {
+        self.modifiers.iter().any(|modifier| modifier.kind == kind)
+    }
+
+    /// A Tile Charm favours the kind an unfinished collect objective wants,
+    /// or leaves when there isn't one.
+    fn weight_shift_target(&self) -> TileType {
+        self.objectives
+            .iter()
+            .filter(|tracker| !tracker.complete)
+            .find_map(|tracker| match tracker.goal {
+                LevelObjective::CollectTiles { kind, .. } if spawn::spawn_index(kind).is_some() => Some(kind),
+                _ => None,
+            })
+            .unwrap_or(TileType::Leaf)
+    }
+
+    /// Use one carried modifier. Returns false if there's none to use, the
+    /// board is busy, or it would do nothing (already active, no brambles).
+    pub(crate) fn use_board_modifier(&mut self, kind: BoardModifierKind) -> bool {
+        let item = ItemType::BoardModifier(kind);
+        if targeting::is_targeted(kind) || !self.board_idle() || economy::inventory_count(&self.inventory, item) == 0 {
+            return false;
+        }
+
+        match modifier_duration(kind) {
+            None => {
+                // Fog Clear: break one bramble.
+                let brambles: Vec<(usize, usize)> =
+                    self.grid.cells().into_iter().filter(|(x, y)| self.grid[*x][*y].blocker).collect();
+                if brambles.is_empty() {
+                    return false;
+                }
+                let (x, y) = brambles[self.rng.board.gen_range(0, brambles.len())];
+                self.grid[x][y].blocker = false;
+            }
+            Some(duration) => {
+                if self.has_modifier(kind) {
+                    return false;
+                }
+                let target = (kind == BoardModifierKind::TileWeightShift).then(|| self.weight_shift_target());
+                self.modifiers.push(ActiveModifier { kind, duration, level: self.level, biome: self.biome_index(), target });
+                if let Some(target) = target {
+                    self.grid.spawn_table_mut().add_modifier(target, WEIGHT_SHIFT_PERCENT);
+                }
+            }
+        }
+        self.inventory.take_one(item);
+        self.drop_undo();
+        true
+    }
+
+    /// Drop modifiers whose level or biome is over. Call when a level starts.
+    pub(crate) fn expire_modifiers(&mut self) {
+        let (level, biome) = (self.level, self.biome_index());
+        self.modifiers.retain(|modifier| match modifier.duration {
+            ModifierDuration::Level => modifier.level == level,
+            ModifierDuration::Biome => modifier.biome == biome,
+        });
+    }
+
+    /// Put active Tile Charms back on a freshly set spawn table.
+    pub(crate) fn apply_modifier_spawn_shifts(&mut self) {
+        let targets: Vec<TileType> = self.modifiers.iter().filter_map(|modifier| modifier.target).collect();
+        for target in targets {
+            self.grid.spawn_table_mut().add_modifier(target, WEIGHT_SHIFT_PERCENT);
+        }
+    }
+
+    /// What the next illicit move will cost, after Cost Cap and Spiced Nut.
+    pub(crate) fn illicit_charge(&self) -> i32 {
+        let cost = if self.has_modifier(BoardModifierKind::IllegalCostCap) {
+            self.illegal_move_cost.min(ILLEGAL_COST_CAP)
+        } else {
+            self.illegal_move_cost
+        };
+        (cost - self.illicit_discount()).max(0)
+    }
+
+    /// Extra points for a clear deep in a cascade.
+    pub(crate) fn cascade_bonus(&self, chain: u32) -> i32 {
+        if chain >= CASCADE_BONUS_FROM_CHAIN && self.has_modifier(BoardModifierKind::CascadeBonus) {
+            CASCADE_BONUS_POINTS
+        } else {
+            0
+        }
+    }
+
+    pub(crate) fn modifier_badges(&self) -> Vec<ModifierBadge> {
+        self.modifiers
+            .iter()
+            .map(|modifier| {
+                let (label, color) = modifier_badge_style(modifier.kind);
+                let effect = match modifier.kind {
+                    BoardModifierKind::TileWeightShift => {
+                        format!("More {}", objectives::kind_name(modifier.target.unwrap_or(TileType::Leaf)))
+                    }
+                    BoardModifierKind::IllegalCostCap => format!("Illicit max {}", ILLEGAL_COST_CAP),
+                    BoardModifierKind::CascadeBonus => format!("+{} deep cascades", CASCADE_BONUS_POINTS),
+                    _ => String::new(),
+                };
+                let until = match modifier.duration {
+                    ModifierDuration::Level => "level",
+                    ModifierDuration::Biome => "biome",
+                };
+                ModifierBadge { label, color, detail: format!("{} ({})", effect, until) }
+            })
+            .collect()
+    }
+
+    /// Carried modifiers with their number key, for the HUD.
+    pub(crate) fn carried_modifier_lines(&self) -> Vec<String> {
+        MODIFIER_KINDS
+            .iter()
+            .enumerate()
+            .filter_map(|(index, kind)| {
+                let count = economy::inventory_count(&self.inventory, ItemType::BoardModifier(*kind));
+                (count > 0).then(|| {
+                    format!("[{}] {} x{}", index + 1, economy::item_name(ItemType::BoardModifier(*kind)), count)
+                })
+            })
+            .collect()
+    }
+}
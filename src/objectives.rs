@@ -0,0 +1,196 @@
+// --- Level objectives ---
+//
+// A level from the level file can ask for more than its score target. Each
+// objective gets a tracker when the level starts, and the level only clears
+// once the score target is met and every tracker is complete.
+
+use crate::game_state::GameState;
+use crate::match_logic::MatchCell;
+use crate::tile::{Tile, TileType};
+
+pub const INGREDIENTS_ON_BOARD: i32 = 2; // most ingredients waiting on the board at once
+
+/// A goal a level can set on top of its score target.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LevelObjective {
+    CollectTiles { kind: TileType, count: u32 },
+    ClearBlockers,
+    BringIngredients { count: u32 },
+    ScoreWithinMoves { points: i32, moves: u32 },
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ObjectiveTracker {
+    pub goal: LevelObjective,
+    pub progress: i32, // tiles, brambles, ingredients or points so far
+    pub complete: bool,
+    pub failed: bool, // ScoreWithinMoves ran out of moves; the level is lost
+}
+
+pub(crate) fn kind_name(kind: TileType) -> &'static str {
+    match kind {
+        TileType::Sun => "Sun",
+        TileType::Moon => "Moon",
+        TileType::Water => "Water",
+        TileType::Leaf => "Leaf",
+        TileType::Exotic => "Exotic",
+        TileType::Ingredient => "Ingredient",
+        TileType::Empty => "Empty",
+    }
+}
+
+impl GameState {
+    /// Fresh trackers for the current level, and the first ingredients.
+    pub(crate) fn begin_level_objectives(&mut self) {
+        let goals = self.level_def(self.level).map(|def| def.objectives.clone()).unwrap_or_default();
+        self.objectives = goals
+            .into_iter()
+            .map(|goal| ObjectiveTracker {
+                goal,
+                progress: 0,
+                complete: false,
+                failed: false,
+            })
+            .collect();
+        self.top_up_ingredients();
+    }
+
+    pub(crate) fn record_cleared_tiles(&mut self, cells: &[MatchCell]) {
+        for tracker in &mut self.objectives {
+            if let LevelObjective::CollectTiles { kind, count } = tracker.goal {
+                tracker.progress += cells.iter().filter(|(_, _, cleared)| *cleared == kind).count() as i32;
+                tracker.complete = tracker.progress >= count as i32;
+            }
+        }
+    }
+
+    /// Refresh the objectives that read the board or the score. Call once the
+    /// board is idle so a cascade in flight isn't judged half-way.
+    pub(crate) fn update_objectives(&mut self) {
+        let blockers_left = self.grid.tiles().filter(|tile| tile.blocker).count() as i32;
+        let blockers_total = self.level_def(self.level).map_or(0, |def| def.blockers.len() as i32);
+        for tracker in &mut self.objectives {
+            match tracker.goal {
+                LevelObjective::ClearBlockers => {
+                    tracker.progress = blockers_total - blockers_left;
+                    tracker.complete = blockers_left == 0;
+                }
+                LevelObjective::ScoreWithinMoves { points, moves } if !tracker.complete => {
+                    tracker.progress = self.total_points - self.level_start_points;
+                    tracker.complete = tracker.progress >= points;
+                    tracker.failed = !tracker.complete && self.moves_made >= moves;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    pub(crate) fn objectives_complete(&self) -> bool {
+        self.objectives.iter().all(|tracker| tracker.complete)
+    }
+
+    pub(crate) fn objective_failed(&self) -> bool {
+        self.objectives.iter().any(|tracker| tracker.failed)
+    }
+
+    /// HUD lines with whether each objective is done.
+    pub(crate) fn objective_lines(&self) -> Vec<(String, bool)> {
+        let blockers_total = self.level_def(self.level).map_or(0, |def| def.blockers.len());
+        self.objectives
+            .iter()
+            .map(|tracker| {
+                let text = match tracker.goal {
+                    LevelObjective::CollectTiles { kind, count } => {
+                        format!("Collect {}: {}/{}", kind_name(kind), tracker.progress.min(count as i32), count)
+                    }
+                    LevelObjective::ClearBlockers => format!("Break brambles: {}/{}", tracker.progress, blockers_total),
+                    LevelObjective::BringIngredients { count } => format!("Ingredients: {}/{}", tracker.progress, count),
+                    LevelObjective::ScoreWithinMoves { points, moves } if tracker.complete => {
+                        format!("Score {} in {} moves: done", points, moves)
+                    }
+                    LevelObjective::ScoreWithinMoves { points, moves } => format!(
+                        "Score {} in {} moves: {} ({} left)",
+                        points,
+                        moves,
+                        tracker.progress,
+                        moves.saturating_sub(self.moves_made),
+                    ),
+                };
+                (text, tracker.complete)
+            })
+            .collect()
+    }
+
+    /// Ingredients the level still needs that aren't already on the board.
+    fn ingredients_to_spawn(&self) -> i32 {
+        let on_board = self.grid.tiles().filter(|tile| tile.kind == TileType::Ingredient).count() as i32;
+        let wanted = self
+            .objectives
+            .iter()
+            .map(|tracker| match tracker.goal {
+                LevelObjective::BringIngredients { count } => count as i32 - tracker.progress,
+                _ => 0,
+            })
+            .sum::<i32>();
+        (wanted - on_board).min(INGREDIENTS_ON_BOARD - on_board).max(0)
+    }
+
+    /// Swap fresh ingredients into the top of random columns. They keep the
+    /// replaced tile's fall so they drop in with the refill.
+    fn top_up_ingredients(&mut self) {
+        for _ in 0..self.ingredients_to_spawn() {
+            let columns: Vec<(usize, usize)> = (0..self.grid.width())
+                .filter_map(|x| {
+                    let rows = self.grid.column_cells(x);
+                    let top = *rows.first()?;
+                    let tile = self.grid[x][top];
+                    (rows.len() > 1 && tile.kind.is_gem() && !tile.blocker && tile.special.is_none()).then_some((x, top))
+                })
+                .collect();
+            if columns.is_empty() {
+                return;
+            }
+            let (x, y) = columns[self.rng.board.gen_range(0, columns.len())];
+            let replaced = self.grid[x][y];
+            self.grid[x][y] = Tile {
+                offset_y: replaced.offset_y,
+                fall_velocity: replaced.fall_velocity,
+                fall_delay: replaced.fall_delay,
+                ..Tile::ingredient()
+            };
+        }
+    }
+
+    /// Take any ingredient sitting in the lowest cell of its column off the
+    /// board, let the column fall and top the ingredients back up. Returns
+    /// whether anything was delivered.
+    pub(crate) fn deliver_ingredients(&mut self) -> bool {
+        let mut delivered_any = false;
+        loop {
+            let mut delivered = 0;
+            for x in 0..self.grid.width() {
+                if let Some(&y) = self.grid.column_cells(x).last() {
+                    if self.grid[x][y].kind == TileType::Ingredient {
+                        self.grid[x][y] = Tile::EMPTY;
+                        delivered += 1;
+                    }
+                }
+            }
+            if delivered == 0 {
+                break;
+            }
+            delivered_any = true;
+            for tracker in &mut self.objectives {
+                if let LevelObjective::BringIngredients { count } = tracker.goal {
+                    tracker.progress += delivered;
+                    tracker.complete = tracker.progress >= count as i32;
+                }
+            }
+            self.apply_gravity();
+        }
+        if delivered_any {
+            self.top_up_ingredients();
+        }
+        delivered_any
+    }
+}
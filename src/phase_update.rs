@@ -1,106 +1,208 @@
 use macroquad::prelude::*;
 
-use crate::game_state::{GamePhase, GameState, GardenTool, LEVEL_TARGET_STEP, LEVELS_PER_SET, ISO_TILE_HW, ISO_TILE_HH, ISO_LEFT_ORIGIN_NX, ISO_LEFT_ORIGIN_NY, ISO_DOT_RADIUS};
+use crate::game_state::{drawer_tools, GamePhase, GameState, GardenTool, ISO_TILE_HW, ISO_TILE_HH, ISO_LEFT_ORIGIN_NX, ISO_LEFT_ORIGIN_NY, ISO_DOT_RADIUS};
 use crate::ui_layout::{
     garden_hunt_button_rect,
     garden_return_button_rect,
+    garden_shed_button_rect,
     hunt_return_button_rect,
     point_in_rect,
 };
 use crate::inventory::ItemType;
 use crate::garden::PlantType;
+use crate::replay::{PhaseAction, ReplayAction};
 
 impl GameState {
-    pub(crate) fn update_level_transition(&mut self) {
-        if is_key_pressed(KeyCode::Enter) {
-            if self.level % LEVELS_PER_SET == 0 {
+    /// Every screen-to-screen transition goes through here, so live input and
+    /// replays share one code path.
+    pub(crate) fn apply_phase_action(&mut self, action: PhaseAction) {
+        match action {
+            PhaseAction::VisitGarden => {
+                // The garden can spend what the snapshot would hand back.
+                self.drop_undo();
+                self.phase = GamePhase::Garden;
+            }
+            PhaseAction::DescendFromFarming => self.phase = GamePhase::LevelTransition,
+            PhaseAction::Descend => {
+                if self.next_level_changes_biome() {
+                    self.reset_illegal_move_cost();
+                    self.phase = GamePhase::Shop;
+                } else {
+                    self.advance_level();
+                    self.phase = GamePhase::Playing;
+                }
+                self.is_farming = false;
+            }
+            PhaseAction::Farm => {
+                self.is_farming = true;
+                self.phase = GamePhase::Playing;
+            }
+            PhaseAction::ShopSpend => {
+                if self.get_leaves_wallet() >= 500 {
+                    self.spent_points += 500;
+                }
+            }
+            PhaseAction::LeaveShop => {
+                // Reset first so the new level starts from the reset cost.
                 self.reset_illegal_move_cost();
-                self.phase = GamePhase::Shop;
-            } else {
-                self.level += 1;
-                self.target += LEVEL_TARGET_STEP;
+                self.advance_level();
                 self.phase = GamePhase::Playing;
             }
-            self.is_farming = false;
+            PhaseAction::ReturnToPuzzle => self.phase = GamePhase::Playing,
+            PhaseAction::GoHunt => self.phase = GamePhase::Hunt,
+            PhaseAction::LeaveHunt => self.phase = GamePhase::Garden,
+            PhaseAction::RetryLevel => self.retry_level(),
+        }
+    }
+
+    pub(crate) fn update_level_transition(&mut self) {
+        if !self.accepts_live_input() {
+            return;
+        }
+
+        if is_key_pressed(KeyCode::Enter) {
+            self.perform_action(ReplayAction::Phase(PhaseAction::Descend));
         }
 
         if is_key_pressed(KeyCode::F) {
-            self.is_farming = true;
-            self.phase = GamePhase::Playing;
+            self.perform_action(ReplayAction::Phase(PhaseAction::Farm));
+        }
+    }
+
+    pub(crate) fn update_level_failed(&mut self) {
+        if !self.accepts_live_input() {
+            return;
+        }
+
+        if is_key_pressed(KeyCode::Enter) {
+            self.perform_action(ReplayAction::Phase(PhaseAction::RetryLevel));
         }
     }
 
     pub(crate) fn update_shop(&mut self) {
+        if !self.accepts_live_input() {
+            return;
+        }
+
         if is_key_pressed(KeyCode::Enter) {
-            self.level += 1;
-            self.target += LEVEL_TARGET_STEP;
-            self.reset_illegal_move_cost();
-            self.phase = GamePhase::Playing;
+            self.perform_action(ReplayAction::Phase(PhaseAction::LeaveShop));
         }
 
         if is_key_pressed(KeyCode::Space) && self.get_leaves_wallet() >= 500 {
-            self.spent_points += 500;
+            self.perform_action(ReplayAction::Phase(PhaseAction::ShopSpend));
         }
+
+        self.handle_food_keys();
+        self.handle_upgrade_keys();
     }
 
     pub(crate) fn update_garden(&mut self) {
-        let now_unix = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs() as i64;
-        self.garden.tick_all(now_unix);
+        self.garden.tick_all(self.clock_now());
+
+        if !self.accepts_live_input() {
+            return;
+        }
+
+        if self.discard_confirm.is_some() {
+            self.update_discard_confirm();
+            return;
+        }
+
+        if self.inventory_open {
+            self.update_inventory_screen();
+            return;
+        }
+
+        if self.shed_open {
+            self.update_shed_screen();
+            return;
+        }
+
+        if is_key_pressed(KeyCode::Tab) {
+            self.toggle_inventory_screen();
+            return;
+        }
+
+        if is_key_pressed(KeyCode::S) {
+            self.toggle_shed_screen();
+            return;
+        }
 
         if is_key_pressed(KeyCode::Escape) {
-            if self.garden_selected_tool.is_some() {
-                self.garden_selected_tool = None;
-            } else if self.garden_drawer_open {
-                self.garden_drawer_open = false;
-            } else {
-                self.phase = GamePhase::Playing;
-            }
+            self.perform_action(ReplayAction::GardenEscape);
             return;
         }
 
         if is_key_pressed(KeyCode::I) {
-            self.garden_drawer_open = !self.garden_drawer_open;
-            if !self.garden_drawer_open {
-                self.garden_selected_tool = None;
+            self.perform_action(ReplayAction::GardenToggleDrawer);
+        }
+
+        // Right-clicking a drawer button offers that item to Bagira.
+        if is_mouse_button_pressed(MouseButton::Right) {
+            let (mx, my) = mouse_position();
+            if let Some(slot) = self.check_drawer_button_click(mx, my).and_then(|tool| self.find_inventory_slot_for_tool(tool)) {
+                self.request_discard(slot);
             }
         }
 
         if is_mouse_button_pressed(MouseButton::Left) {
             let (mx, my) = mouse_position();
-            let (rx, ry, rw, rh) = garden_return_button_rect();
-            let (hx, hy, hw, hh) = garden_hunt_button_rect();
+            self.perform_action(ReplayAction::GardenClick {
+                nx: mx / screen_width(),
+                ny: my / screen_height(),
+            });
+        }
+    }
 
-            if point_in_rect(mx, my, rx, ry, rw, rh) {
-                self.phase = GamePhase::Playing;
-                return;
-            } else if point_in_rect(mx, my, hx, hy, hw, hh) {
-                self.phase = GamePhase::Hunt;
-                return;
-            }
+    pub(crate) fn handle_garden_escape(&mut self) {
+        if self.garden_selected_tool.is_some() {
+            self.garden_selected_tool = None;
+        } else if self.garden_drawer_open {
+            self.garden_drawer_open = false;
+        } else {
+            self.apply_phase_action(PhaseAction::ReturnToPuzzle);
+        }
+    }
 
-            if self.check_drawer_toggle_click(mx, my) {
-                self.garden_drawer_open = !self.garden_drawer_open;
-                if !self.garden_drawer_open {
-                    self.garden_selected_tool = None;
-                }
-                return;
-            }
+    pub(crate) fn toggle_garden_drawer(&mut self) {
+        self.garden_drawer_open = !self.garden_drawer_open;
+        if !self.garden_drawer_open {
+            self.garden_selected_tool = None;
+        }
+    }
 
-            // Check drawer tool buttons
-            if let Some(tool) = self.check_drawer_button_click(mx, my) {
-                self.garden_selected_tool = Some(tool);
-                return;
-            }
+    pub(crate) fn handle_garden_click(&mut self, mx: f32, my: f32) {
+        let (rx, ry, rw, rh) = garden_return_button_rect();
+        let (hx, hy, hw, hh) = garden_hunt_button_rect();
+        let (shx, shy, shw, shh) = garden_shed_button_rect();
 
-            // If tool is active, try clicking a plot
-            if let Some(tool) = self.garden_selected_tool {
-                if let Some(plot_idx) = self.point_to_plot_index(mx, my) {
-                    self.apply_garden_tool(tool, plot_idx, now_unix);
-                    self.garden_selected_tool = None;
-                }
+        if point_in_rect(mx, my, rx, ry, rw, rh) {
+            self.apply_phase_action(PhaseAction::ReturnToPuzzle);
+            return;
+        } else if point_in_rect(mx, my, hx, hy, hw, hh) {
+            self.apply_phase_action(PhaseAction::GoHunt);
+            return;
+        } else if point_in_rect(mx, my, shx, shy, shw, shh) {
+            self.toggle_shed_screen();
+            return;
+        }
+
+        if self.check_drawer_toggle_click(mx, my) {
+            self.toggle_garden_drawer();
+            return;
+        }
+
+        // Check drawer tool buttons
+        if let Some(tool) = self.check_drawer_button_click(mx, my) {
+            self.garden_selected_tool = Some(tool);
+            return;
+        }
+
+        // If tool is active, try clicking a plot
+        if let Some(tool) = self.garden_selected_tool {
+            if let Some(plot_idx) = self.point_to_plot_index(mx, my) {
+                self.apply_garden_tool(tool, plot_idx, self.clock_now());
+                self.garden_selected_tool = None;
             }
         }
     }
@@ -135,18 +237,12 @@ impl GameState {
         let btn_start_y = drawer_y + drawer_h * 0.24;
         let spacing = sw * 0.012;
 
-        let buttons = [
-            ("CAN", GardenTool::Water),
-            ("SUN", GardenTool::PlantSun),
-            ("MON", GardenTool::PlantMoon),
-            ("ESS", GardenTool::PlantEssence),
-            ("FERT", GardenTool::Fertilize),
-        ];
+        let buttons = drawer_tools(&self.inventory);
 
         let total_w = buttons.len() as f32 * btn_size + (buttons.len() as f32 - 1.0) * spacing;
         let start_x = (sw - total_w) * 0.5;
 
-        for (idx, (_, tool)) in buttons.iter().enumerate() {
+        for (idx, tool) in buttons.iter().enumerate() {
             let btn_x = start_x + idx as f32 * (btn_size + spacing);
             if point_in_rect(mx, my, btn_x, btn_start_y, btn_size, btn_size) {
                 return Some(*tool);
@@ -232,27 +328,25 @@ impl GameState {
     }
 
     fn find_inventory_slot_for_tool(&self, tool: GardenTool) -> Option<usize> {
-        let item = match tool {
-            GardenTool::Water => ItemType::WateringCan,
-            GardenTool::PlantSun => ItemType::SeedDay,
-            GardenTool::PlantMoon => ItemType::SeedNight,
-            GardenTool::PlantEssence => ItemType::MoonbloomEssence,
-            GardenTool::Fertilize => ItemType::Fertilizer,
-        };
-        self.inventory.slots.iter().position(|slot| slot.item == Some(item))
+        self.inventory.slots.iter().position(|slot| slot.item == Some(tool.item()))
     }
 
     pub(crate) fn update_hunt(&mut self) {
+        if !self.accepts_live_input() {
+            return;
+        }
+
         if is_mouse_button_pressed(MouseButton::Left) {
             let (mx, my) = mouse_position();
             let (bx, by, bw, bh) = hunt_return_button_rect();
             if point_in_rect(mx, my, bx, by, bw, bh) {
-                self.phase = GamePhase::Garden;
+                self.perform_action(ReplayAction::Phase(PhaseAction::LeaveHunt));
+                return;
             }
         }
 
         if is_key_pressed(KeyCode::Escape) {
-            self.phase = GamePhase::Garden;
+            self.perform_action(ReplayAction::Phase(PhaseAction::LeaveHunt));
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,134 @@
+// --- Floating popups ---
+//
+// Text that rises off the board when a clear lands: the points it scored, a
+// cascade call-out, and anything that dropped. Popups live in board cell
+// coordinates so they stay over their tiles if the window is resized.
+
+use macroquad::prelude::*;
+
+use crate::economy::{self, ResourceDrop};
+use crate::game_state::GameState;
+use crate::match_logic::{self, MatchCell};
+use crate::scoring::ClearScore;
+
+pub const POPUP_LIFE: f32 = 1.1;          // seconds on screen, after any delay
+pub const POPUP_RISE_CELLS: f32 = 0.9;    // how far a popup drifts up, in cells
+pub const POPUP_DROP_STAGGER: f32 = 0.12; // gap between drop popups from one clear
+pub const POPUP_MAX_DROPS: usize = 4;     // extra drops in one clear fold into the last popup
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PopupStyle {
+    Score,
+    Cascade,
+    Drop, // drawn with an icon dot in `color`
+}
+
+#[derive(Clone, Debug)]
+pub struct FloatingPopup {
+    pub x: f32, // cell coordinates of the popup's centre
+    pub y: f32,
+    pub text: String,
+    pub color: Color,
+    pub style: PopupStyle,
+    pub delay: f32, // seconds before it appears
+    pub age: f32,
+}
+
+impl FloatingPopup {
+    fn new(x: f32, y: f32, text: String, color: Color, style: PopupStyle, delay: f32) -> Self {
+        Self { x, y, text, color, style, delay, age: 0.0 }
+    }
+
+    pub fn visible(&self) -> bool {
+        self.delay <= 0.0
+    }
+
+    /// 0.0 when it appears, 1.0 when it's gone.
+    pub fn progress(&self) -> f32 {
+        (self.age / POPUP_LIFE).clamp(0.0, 1.0)
+    }
+}
+
+fn drop_text(drop: ResourceDrop) -> String {
+    match drop {
+        ResourceDrop::Leaves(leaves) => format!("+{} Leaves", leaves),
+        ResourceDrop::Item(item) => format!("+1 {}", economy::item_name(item)),
+        ResourceDrop::Consigned { item, paid } => format!("{} sold +{}", economy::item_name(item), paid),
+        ResourceDrop::Stored(item) => format!("+1 {} to shed", economy::item_name(item)),
+    }
+}
+
+pub fn update_popups(popups: &mut Vec<FloatingPopup>, delta: f32) {
+    for popup in popups.iter_mut() {
+        if popup.delay > 0.0 {
+            popup.delay -= delta;
+        } else {
+            popup.age += delta;
+        }
+    }
+    popups.retain(|popup| popup.age < POPUP_LIFE);
+}
+
+impl GameState {
+    /// Popups for one finished clear: its points over the middle of the
+    /// cleared cells, a call-out when it was a cascade, then each drop from
+    /// the cell that dropped it.
+    pub(crate) fn spawn_clear_popups(
+        &mut self,
+        cells: &[MatchCell],
+        score: &ClearScore,
+        bonus: i32,
+        drops: &[(usize, usize, ResourceDrop)],
+    ) {
+        if cells.is_empty() {
+            return;
+        }
+
+        let count = cells.len() as f32;
+        let center_x = cells.iter().map(|(x, _, _)| *x as f32).sum::<f32>() / count;
+        let center_y = cells.iter().map(|(_, y, _)| *y as f32).sum::<f32>() / count;
+        let points = score.total + bonus;
+        if points > 0 {
+            self.popups.push(FloatingPopup::new(
+                center_x,
+                center_y,
+                format!("+{}", points),
+                match_logic::tile_particle_color(cells[0].2),
+                PopupStyle::Score,
+                0.0,
+            ));
+        }
+
+        if score.chain > 0 {
+            let announce_x = (self.grid.width() as f32 - 1.0) * 0.5;
+            let announce_y = (self.grid.height() as f32 - 1.0) * 0.5;
+            // A quick cascade replaces the call-out still on screen rather than stacking.
+            self.popups.retain(|popup| popup.style != PopupStyle::Cascade);
+            self.popups.push(FloatingPopup::new(
+                announce_x,
+                announce_y,
+                format!("x{} Cascade!", score.multiplier),
+                color_u8!(255, 235, 150, 255),
+                PopupStyle::Cascade,
+                0.0,
+            ));
+        }
+
+        for (index, &(x, y, drop)) in drops.iter().enumerate().take(POPUP_MAX_DROPS) {
+            let text = if index + 1 == POPUP_MAX_DROPS && drops.len() > POPUP_MAX_DROPS {
+                format!("+{} more drops", drops.len() - index)
+            } else {
+                drop_text(drop)
+            };
+            let kind = cells.iter().find(|cell| (cell.0, cell.1) == (x, y)).map_or(cells[0].2, |cell| cell.2);
+            self.popups.push(FloatingPopup::new(
+                x as f32,
+                y as f32,
+                text,
+                match_logic::tile_particle_color(kind),
+                PopupStyle::Drop,
+                POPUP_DROP_STAGGER * (index + 1) as f32,
+            ));
+        }
+    }
+}
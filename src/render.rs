@@ -1,12 +1,12 @@
 use macroquad::prelude::*;
 
 use crate::economy;
+use crate::scoring::ScoreBreakdown;
 use crate::garden::{Garden, PlantStage, PlantType};
 use crate::game_state::{
+    drawer_tools,
     GameState,
     GardenTool,
-    GRID_HEIGHT,
-    GRID_WIDTH,
     LEAF_AUX_SWAY_AMP_DEG,
     LEAF_AUX_SWAY_PHASE,
     LEAF_AUX_SWAY_PIVOT_NX,
@@ -16,8 +16,8 @@ use crate::game_state::{
     LEAF_SWAY_PIVOT_NY,
     LEAF_SWAY_SPEED,
     LEAF_SWAY_SPEED_2,
-    LEVELS_PER_SET,
     MATCH_CLEAR_DELAY,
+    SHUFFLE_DURATION,
     CAVE_GRASS_SWAY_SPEED,
     CAVE_GRASS_SWAY_SPEED_2,
     CAVE_GRASS_SWAY_AMP_PX,
@@ -39,17 +39,30 @@ use crate::game_state::{
 };
 use crate::inventory::{Inventory, ItemType};
 use crate::match_logic;
-use crate::tile::TileType;
+use crate::buffs;
+use crate::discard;
+use crate::modifiers::{self, ModifierBadge};
+use crate::popups::{FloatingPopup, PopupStyle, POPUP_RISE_CELLS};
+use crate::targeting::{self, ArmedItem};
+use crate::tile::{SpecialGem, TileType};
 use crate::ui_layout::{
     garden_hunt_button_rect,
     garden_return_button_rect,
+    garden_shed_button_rect,
     hunt_return_button_rect,
+    inventory_screen_slot_rect,
+    inventory_sort_button_rect,
     playing_descend_button_rect,
+    playing_inventory_slot_rect,
+    playing_undo_button_rect,
     playing_visit_garden_button_rect,
+    point_in_rect,
+    shed_bag_slot_rect,
+    shed_slot_rect,
     Layout,
 };
 
-pub fn draw_shop_screen(leaves_wallet: i32) {
+pub fn draw_shop_screen(leaves_wallet: i32, food_lines: &[String], buff_badges: &[ModifierBadge], upgrade_lines: &[String]) {
     let sw = screen_width();
     let sh = screen_height();
     draw_text("THE SHRINE", sw * 0.25, sh * 0.17, (sh * 0.103).max(40.0), PURPLE);
@@ -67,8 +80,37 @@ pub fn draw_shop_screen(leaves_wallet: i32) {
         (sh * 0.052).max(22.0),
         WHITE,
     );
+
+    // Food to eat before the next biome, and what's already been eaten.
+    let food_font = (sh * 0.032).max(15.0);
+    for (index, line) in food_lines.iter().enumerate() {
+        draw_text(line, sw * 0.19, sh * 0.45 + index as f32 * food_font * 1.2, food_font, color_u8!(220, 230, 200, 255));
+    }
+    for (index, badge) in buff_badges.iter().enumerate() {
+        let text = format!("{}: {}", badge.label, badge.detail);
+        draw_text(&text, sw * 0.55, sh * 0.45 + index as f32 * food_font * 1.2, food_font, badge.color);
+    }
+
+    // Bagira's satchel upgrades.
+    for (index, line) in upgrade_lines.iter().enumerate() {
+        draw_text(line, sw * 0.19, sh * 0.78 + index as f32 * food_font * 1.2, food_font, color_u8!(230, 200, 140, 255));
+    }
 }
 
+/// Chip label and colour for an item in the HUD satchel.
+fn inventory_chip(item: ItemType) -> (&'static str, Color) {
+    match item {
+        ItemType::WateringCan => ("CAN", color_u8!(55, 125, 210, 255)),
+        ItemType::SeedDay => ("SUN", color_u8!(210, 165, 35, 255)),
+        ItemType::SeedNight => ("MON", color_u8!(100, 125, 220, 255)),
+        ItemType::MoonbloomEssence => ("ESS", color_u8!(160, 90, 220, 255)),
+        ItemType::Fertilizer => ("FERT", color_u8!(185, 55, 55, 255)),
+        ItemType::BoardModifier(kind) => modifiers::modifier_badge_style(kind),
+        ItemType::FoodBuff(kind) => buffs::buff_badge_style(kind),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn draw_inventory_slot(
     x: f32,
     y: f32,
@@ -370,6 +412,7 @@ fn draw_garden_plot_state(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn draw_garden_screen(
     garden_bg_texture: &Texture2D,
     iso_left_origin_nx: f32,
@@ -458,6 +501,17 @@ pub fn draw_garden_screen(
         WHITE,
     );
 
+    let (shx, shy, shw, shh) = garden_shed_button_rect();
+    draw_rectangle(shx, shy, shw, shh, color_u8!(70, 52, 34, 255));
+    draw_rectangle_lines(shx, shy, shw, shh, 3.0, color_u8!(210, 170, 120, 255));
+    draw_text(
+        "SHED [S]",
+        shx + shw * 0.22,
+        shy + shh * 0.62,
+        (shh * 0.48).max(20.0),
+        WHITE,
+    );
+
     // Draw inventory drawer at bottom
     draw_garden_drawer(sw, sh, inventory, selected_tool, drawer_open);
 }
@@ -506,13 +560,11 @@ fn draw_garden_drawer(
     let btn_start_y = drawer_y + drawer_h * 0.24;
     let spacing = sw * 0.012;
 
-    let buttons = [
-        ("CAN", GardenTool::Water, economy::inventory_count(inventory, ItemType::WateringCan), color_u8!(55, 125, 210, 255)),
-        ("SUN", GardenTool::PlantSun, economy::inventory_count(inventory, ItemType::SeedDay), color_u8!(210, 165, 35, 255)),
-        ("MON", GardenTool::PlantMoon, economy::inventory_count(inventory, ItemType::SeedNight), color_u8!(100, 125, 220, 255)),
-        ("ESS", GardenTool::PlantEssence, economy::inventory_count(inventory, ItemType::MoonbloomEssence), color_u8!(160, 90, 220, 255)),
-        ("FERT", GardenTool::Fertilize, economy::inventory_count(inventory, ItemType::Fertilizer), color_u8!(185, 55, 55, 255)),
-    ];
+    // Buttons follow the satchel's slot order.
+    let buttons = drawer_tools(inventory).map(|tool| {
+        let (label, color) = inventory_chip(tool.item());
+        (label, tool, economy::inventory_count(inventory, tool.item()), color)
+    });
 
     let total_w = buttons.len() as f32 * btn_size + (buttons.len() as f32 - 1.0) * spacing;
     let start_x = (sw - total_w) * 0.5;
@@ -538,7 +590,7 @@ fn draw_garden_drawer(
         );
     }
 
-    draw_text("[I] Toggle", sw * 0.02, drawer_y + drawer_h * 0.66, (font_sm * 0.75).max(10.0), color_u8!(180, 200, 180, 190));
+    draw_text("[I] Toggle  [Tab] Satchel  [RMB] Sell", sw * 0.02, drawer_y + drawer_h * 0.66, (font_sm * 0.75).max(10.0), color_u8!(180, 200, 180, 190));
 }
 
 pub fn draw_hunt_screen() {
@@ -572,6 +624,98 @@ pub fn draw_hunt_screen() {
     );
 }
 
+fn draw_special_gem_overlay(gem: SpecialGem, x: f32, y: f32, size: f32, scale: f32, alpha: f32, time: f32) {
+    let cx = x + size * 0.5;
+    let cy = y + size * 0.5;
+    let half = size * 0.5 * scale;
+    let glow = 0.55 + 0.45 * (time * 5.0).sin().abs();
+    let stripe = Color::new(1.0, 1.0, 1.0, 0.75 * glow * alpha);
+
+    match gem {
+        SpecialGem::LineHorizontal => {
+            for offset in [-0.22, 0.22] {
+                draw_line(cx - half * 0.85, cy + half * offset, cx + half * 0.85, cy + half * offset, 3.0, stripe);
+            }
+        }
+        SpecialGem::LineVertical => {
+            for offset in [-0.22, 0.22] {
+                draw_line(cx + half * offset, cy - half * 0.85, cx + half * offset, cy + half * 0.85, 3.0, stripe);
+            }
+        }
+        SpecialGem::Bomb => {
+            draw_circle_lines(cx, cy, half * (0.62 + 0.08 * glow), 3.0, Color::new(1.0, 0.55, 0.25, 0.85 * alpha));
+            draw_circle(cx, cy, half * 0.14, Color::new(1.0, 0.85, 0.45, 0.9 * alpha));
+        }
+        SpecialGem::ColorClear => {
+            let colors = [
+                color_u8!(255, 215, 110, 255),
+                color_u8!(180, 205, 255, 255),
+                color_u8!(90, 170, 255, 255),
+                color_u8!(150, 235, 150, 255),
+                color_u8!(230, 140, 255, 255),
+            ];
+            for (index, color) in colors.iter().enumerate() {
+                let a = time * 2.2 + index as f32 * std::f32::consts::TAU / colors.len() as f32;
+                draw_circle(
+                    cx + a.cos() * half * 0.62,
+                    cy + a.sin() * half * 0.62,
+                    half * 0.13,
+                    Color::new(color.r, color.g, color.b, 0.9 * alpha),
+                );
+            }
+            draw_circle_lines(cx, cy, half * 0.62, 1.5, stripe);
+        }
+    }
+}
+
+/// Bramble over a blocked tile: a few crossed thorny stems.
+fn draw_blocker_overlay(x: f32, y: f32, size: f32, scale: f32, alpha: f32) {
+    let cx = x + size * 0.5;
+    let cy = y + size * 0.5;
+    let half = size * 0.5 * scale;
+    let stem = Color::new(0.24, 0.36, 0.16, 0.9 * alpha);
+    let thorn = Color::new(0.62, 0.72, 0.38, 0.9 * alpha);
+    for (dx, dy) in [(1.0, 1.0), (1.0, -1.0), (1.0, 0.0)] {
+        draw_line(cx - half * 0.8 * dx, cy - half * 0.8 * dy, cx + half * 0.8 * dx, cy + half * 0.8 * dy, 4.0, stem);
+    }
+    for t in [-0.45, 0.0, 0.45] {
+        draw_circle(cx + half * t, cy + half * t, half * 0.07, thorn);
+        draw_circle(cx + half * t, cy - half * t, half * 0.07, thorn);
+    }
+    draw_rectangle_lines(cx - half * 0.9, cy - half * 0.9, half * 1.8, half * 1.8, 2.0, stem);
+}
+
+/// Clear popups rising from their cells, fading out over their last stretch.
+fn draw_popups(popups: &[FloatingPopup], layout: &Layout) {
+    for popup in popups.iter().filter(|popup| popup.visible()) {
+        let t = popup.progress();
+        let alpha = ((1.0 - t) / 0.4).clamp(0.0, 1.0);
+        let (font, rise) = match popup.style {
+            PopupStyle::Score => (layout.tile_size * 0.5, POPUP_RISE_CELLS),
+            PopupStyle::Cascade => (layout.tile_size * (0.8 + 0.2 * (1.0 - t)), POPUP_RISE_CELLS * 0.5),
+            PopupStyle::Drop => (layout.tile_size * 0.36, POPUP_RISE_CELLS * 1.2),
+        };
+        let font = font.max(14.0);
+        let dims = measure_text(&popup.text, None, font as u16, 1.0);
+        let icon = if popup.style == PopupStyle::Drop { font * 0.3 } else { 0.0 };
+        let center_x = layout.grid_offset_x + (popup.x + 0.5) * layout.tile_size;
+        let center_y = layout.grid_offset_y + (popup.y + 0.5 - rise * t) * layout.tile_size;
+        let text_x = center_x - (dims.width + icon * 2.5) * 0.5 + icon * 2.5;
+        let text_y = center_y + dims.offset_y * 0.5;
+
+        if icon > 0.0 {
+            draw_circle(text_x - icon * 1.5, center_y, icon, Color::new(popup.color.r, popup.color.g, popup.color.b, alpha));
+            draw_circle_lines(text_x - icon * 1.5, center_y, icon, 1.5, Color::new(0.0, 0.0, 0.0, 0.6 * alpha));
+        }
+        draw_text(&popup.text, text_x + 2.0, text_y + 2.0, font, Color::new(0.0, 0.0, 0.0, 0.6 * alpha));
+        let color = match popup.style {
+            PopupStyle::Drop => Color::new(0.95, 0.95, 0.9, alpha),
+            _ => Color::new(popup.color.r, popup.color.g, popup.color.b, alpha),
+        };
+        draw_text(&popup.text, text_x, text_y, font, color);
+    }
+}
+
 pub fn draw_board_and_effects(state: &GameState, layout: &Layout) {
     // --- ANIMATION MATH (Global Time) ---
     let time = get_time() as f32;
@@ -611,7 +755,7 @@ pub fn draw_board_and_effects(state: &GameState, layout: &Layout) {
     let current_frame_water_13 = ping_pong_frame(13);
 
     // 1. DRAW GRID
-    let set_idx = ((state.level - 1) / LEVELS_PER_SET) as usize;
+    let set_idx = state.biome_index();
     let is_cave_biome = set_idx == 1;
     let gems = &state.biome_sets[set_idx.min(state.biome_sets.len() - 1)];
     let clear_progress = if !state.pending_matches.is_empty() {
@@ -619,14 +763,37 @@ pub fn draw_board_and_effects(state: &GameState, layout: &Layout) {
     } else {
         0.0
     };
+    // Dead-board shuffle: tiles shrink away, swap at the midpoint, then grow back.
+    let shuffle_scale = if state.is_shuffling() {
+        let progress = 1.0 - state.shuffle_timer / SHUFFLE_DURATION;
+        (1.0 - 2.0 * progress).abs()
+    } else {
+        1.0
+    };
 
-    for x in 0..GRID_WIDTH {
-        for y in 0..GRID_HEIGHT {
+    // Shaped boards get a faint backing so the holes read as holes.
+    if state.grid.has_holes() {
+        for (x, y) in state.grid.cells() {
+            draw_rectangle(
+                layout.grid_offset_x + x as f32 * layout.tile_size + 1.0,
+                layout.grid_offset_y + y as f32 * layout.tile_size + 1.0,
+                layout.tile_size - 2.0,
+                layout.tile_size - 2.0,
+                Color::new(0.0, 0.0, 0.0, 0.28),
+            );
+        }
+    }
+
+    for x in 0..state.grid.width() {
+        for y in 0..state.grid.height() {
+            if !state.grid.is_playable(x, y) {
+                continue;
+            }
             let tile = &state.grid[x][y];
-            let draw_x = layout.grid_offset_x + x as f32 * layout.tile_size;
+            let draw_x = layout.grid_offset_x + x as f32 * layout.tile_size + tile.offset_x;
             let draw_y = layout.grid_offset_y + y as f32 * layout.tile_size + tile.offset_y;
             let matched = match_logic::pending_match_kind_at(&state.pending_matches, x, y).is_some();
-            let scale = if matched { 1.0 - 0.18 * clear_progress } else { 1.0 };
+            let scale = if matched { 1.0 - 0.18 * clear_progress } else { 1.0 } * shuffle_scale;
             let alpha = if matched { 1.0 - clear_progress } else { 1.0 };
             let flash = if matched && clear_progress < 0.18 {
                 1.0 - clear_progress / 0.18
@@ -767,8 +934,17 @@ pub fn draw_board_and_effects(state: &GameState, layout: &Layout) {
                         },
                     );
                 }
+                TileType::Ingredient => {
+                    // Acorn: a nut with a darker cap and stem.
+                    let cx = draw_x + layout.tile_size * 0.5;
+                    let cy = draw_y + layout.tile_size * 0.5;
+                    let r = layout.tile_size * 0.3 * scale;
+                    draw_circle(cx, cy + r * 0.25, r, Color::new(0.72, 0.5, 0.26, alpha));
+                    draw_rectangle(cx - r * 1.05, cy - r * 0.7, r * 2.1, r * 0.6, Color::new(0.42, 0.28, 0.14, alpha));
+                    draw_line(cx, cy - r * 0.7, cx + r * 0.2, cy - r * 1.1, 3.0, Color::new(0.42, 0.28, 0.14, alpha));
+                }
                 _ => {
-                    let base = tile.get_color(state.level);
+                    let base = tile.get_color(set_idx);
                     draw_rectangle(
                         draw_x,
                         draw_y,
@@ -778,6 +954,13 @@ pub fn draw_board_and_effects(state: &GameState, layout: &Layout) {
                     );
                 }
             }
+
+            if let Some(gem) = tile.special {
+                draw_special_gem_overlay(gem, draw_x, draw_y, layout.tile_size, scale, alpha, time);
+            }
+            if tile.blocker {
+                draw_blocker_overlay(draw_x, draw_y, layout.tile_size, scale, alpha);
+            }
         }
     }
 
@@ -795,18 +978,75 @@ pub fn draw_board_and_effects(state: &GameState, layout: &Layout) {
         );
     }
 
+    // Idle hint: gently pulse both tiles of the suggested swap.
+    if let Some(hint) = state.hint {
+        let pulse = 0.5 + 0.5 * (time * 4.0).sin();
+        for (hx, hy) in [hint.from, hint.to] {
+            let draw_x = layout.grid_offset_x + hx as f32 * layout.tile_size + state.grid[hx][hy].offset_x;
+            let draw_y = layout.grid_offset_y + hy as f32 * layout.tile_size + state.grid[hx][hy].offset_y;
+            draw_rectangle(
+                draw_x,
+                draw_y,
+                layout.tile_size - 2.0,
+                layout.tile_size - 2.0,
+                Color::new(1.0, 0.95, 0.7, 0.10 + 0.14 * pulse),
+            );
+            draw_rectangle_lines(
+                draw_x,
+                draw_y,
+                layout.tile_size - 2.0,
+                layout.tile_size - 2.0,
+                2.0 + 2.0 * pulse,
+                Color::new(1.0, 0.92, 0.55, 0.45 + 0.45 * pulse),
+            );
+        }
+    }
+
     // Keep selection feedback above the overlay.
     if let Some((sx, sy)) = state.selected {
         if match_logic::pending_match_kind_at(&state.pending_matches, sx, sy).is_none() {
-            let draw_x = layout.grid_offset_x + sx as f32 * layout.tile_size;
+            let draw_x = layout.grid_offset_x + sx as f32 * layout.tile_size + state.grid[sx][sy].offset_x;
             let draw_y = layout.grid_offset_y + sy as f32 * layout.tile_size + state.grid[sx][sy].offset_y;
             draw_rectangle_lines(draw_x, draw_y, layout.tile_size - 2.0, layout.tile_size - 2.0, 4.0, WHITE);
         }
     }
 
+    // Targeting preview for an armed item: every cell it would hit.
+    let targets = state.targeting_preview(layout);
+    if !targets.is_empty() {
+        let pulse = 0.5 + 0.5 * (time * 6.0).sin();
+        for (tx, ty) in targets {
+            let draw_x = layout.grid_offset_x + tx as f32 * layout.tile_size;
+            let draw_y = layout.grid_offset_y + ty as f32 * layout.tile_size;
+            let size = layout.tile_size - 2.0;
+            draw_rectangle(draw_x, draw_y, size, size, Color::new(1.0, 0.45, 0.3, 0.16 + 0.14 * pulse));
+            draw_rectangle_lines(draw_x, draw_y, size, size, 3.0, Color::new(1.0, 0.6, 0.35, 0.6 + 0.4 * pulse));
+        }
+    }
+
+    // Keyboard cursor: pulsing corner brackets, so it reads apart from the selection box.
+    if let Some((cx, cy)) = state.cursor {
+        let pulse = 0.5 + 0.5 * (time * 5.0).sin();
+        let inset = 2.0 + 2.0 * pulse;
+        let x0 = layout.grid_offset_x + cx as f32 * layout.tile_size + inset;
+        let y0 = layout.grid_offset_y + cy as f32 * layout.tile_size + inset;
+        let size = layout.tile_size - 2.0 - inset * 2.0;
+        let arm = size * 0.3;
+        let color = Color::new(0.55, 0.95, 1.0, 0.75 + 0.25 * pulse);
+        for (corner_x, corner_y, sx, sy) in [
+            (x0, y0, 1.0, 1.0),
+            (x0 + size, y0, -1.0, 1.0),
+            (x0, y0 + size, 1.0, -1.0),
+            (x0 + size, y0 + size, -1.0, -1.0),
+        ] {
+            draw_line(corner_x, corner_y, corner_x + arm * sx, corner_y, 3.0, color);
+            draw_line(corner_x, corner_y, corner_x, corner_y + arm * sy, 3.0, color);
+        }
+    }
+
     if state.cascade_pulse > 0.0 {
-        let board_w = layout.tile_size * GRID_WIDTH as f32;
-        let board_h = layout.tile_size * GRID_HEIGHT as f32;
+        let board_w = layout.tile_size * state.grid.width() as f32;
+        let board_h = layout.tile_size * state.grid.height() as f32;
         draw_rectangle(
             layout.grid_offset_x,
             layout.grid_offset_y,
@@ -821,6 +1061,21 @@ pub fn draw_board_and_effects(state: &GameState, layout: &Layout) {
         );
     }
 
+    if state.is_shuffling() {
+        let board_w = layout.tile_size * state.grid.width() as f32;
+        let board_h = layout.tile_size * state.grid.height() as f32;
+        let font = (layout.tile_size * 0.55).max(18.0);
+        let text = "No moves left - shuffling!";
+        let dims = measure_text(text, None, font as u16, 1.0);
+        draw_text(
+            text,
+            layout.grid_offset_x + (board_w - dims.width) * 0.5,
+            layout.grid_offset_y + board_h * 0.5,
+            font,
+            color_u8!(230, 245, 220, 255),
+        );
+    }
+
     for particle in &state.particles {
         let life_ratio = (particle.life / particle.max_life).clamp(0.0, 1.0);
         let size = particle.size * (0.55 + 0.45 * life_ratio);
@@ -832,6 +1087,8 @@ pub fn draw_board_and_effects(state: &GameState, layout: &Layout) {
         );
     }
 
+    draw_popups(&state.popups, layout);
+
     // --- Animated leaf overlay (Forest Floor only) ---
     if set_idx == 0 {
         if let Some(ref leaves_tex) = state.leaves_main_texture {
@@ -986,6 +1243,7 @@ pub fn draw_board_and_effects(state: &GameState, layout: &Layout) {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn draw_playing_ui(
     layout: &Layout,
     level: i32,
@@ -996,6 +1254,16 @@ pub fn draw_playing_ui(
     illegal_move_cost: i32,
     inventory: &Inventory,
     is_farming: bool,
+    hints_enabled: bool,
+    strict_swaps: bool,
+    strict_forced: bool,
+    undo_fee: Option<i32>,
+    armed: Option<ArmedItem>,
+    modifier_badges: &[ModifierBadge],
+    carried_modifiers: &[String],
+    objectives: &[(String, bool)],
+    level_limit: Option<(String, bool)>,
+    move_score: &ScoreBreakdown,
 ) {
     let bar_x = layout.ui_panel_x;
     let bar_y = layout.grid_offset_y;
@@ -1016,6 +1284,10 @@ pub fn draw_playing_ui(
     let font_lg = (row_h * 1.05).max(18.0);
     let font_sm = (row_h * 0.88).max(15.0);
     draw_text(&format!("Level {}", level), bar_x, bar_y + row_h * 1.0, font_lg, WHITE);
+    if let Some((text, running_low)) = level_limit {
+        let color = if running_low { color_u8!(255, 110, 90, 255) } else { WHITE };
+        draw_text(&text, bar_x + bar_width * 0.5, bar_y + row_h * 1.0, font_lg, color);
+    }
     draw_text(
         &format!("Leaves: {}", leaves_wallet),
         bar_x,
@@ -1024,7 +1296,11 @@ pub fn draw_playing_ui(
         GOLD,
     );
     draw_text(
-        &format!("Illicit Move Cost: {}", illegal_move_cost),
+        &if strict_swaps {
+            "Illicit Move: slides back (strict)".to_owned()
+        } else {
+            format!("Illicit Move Cost: {}", illegal_move_cost)
+        },
         bar_x,
         bar_y + row_h * 3.1,
         font_sm,
@@ -1033,48 +1309,35 @@ pub fn draw_playing_ui(
 
     let inv_title_y = bar_y + row_h * 4.25;
     draw_text(
-        &format!("Inventory {}/8", economy::inventory_used_slots(inventory)),
+        &format!("Inventory {}/{}", economy::inventory_used_slots(inventory), inventory.slot_count()),
         bar_x,
         inv_title_y,
         font_sm,
         WHITE,
     );
 
-    let slot_size = (layout.ui_panel_width * 0.16).min(row_h * 0.98).max(20.0);
-    let slot_gap = layout.ui_panel_width * 0.018;
-    let slot_y = inv_title_y + row_h * 0.28;
-    let chip_font = (font_sm * 0.70).max(11.0);
-    let inventory_chips = [
-        (
-            "CAN",
-            economy::inventory_count(inventory, ItemType::WateringCan),
-            color_u8!(55, 125, 210, 255),
-        ),
-        (
-            "SUN",
-            economy::inventory_count(inventory, ItemType::SeedDay),
-            color_u8!(210, 165, 35, 255),
-        ),
-        (
-            "MON",
-            economy::inventory_count(inventory, ItemType::SeedNight),
-            color_u8!(100, 125, 220, 255),
-        ),
-        (
-            "ESS",
-            economy::inventory_count(inventory, ItemType::MoonbloomEssence),
-            color_u8!(160, 90, 220, 255),
-        ),
-        (
-            "FERT",
-            economy::inventory_count(inventory, ItemType::Fertilizer),
-            color_u8!(185, 55, 55, 255),
-        ),
-    ];
-
-    for (index, (label, count, color)) in inventory_chips.iter().enumerate() {
-        let x = bar_x + index as f32 * (slot_size + slot_gap);
-        draw_inventory_slot(x, slot_y, slot_size, label, *count, *color, chip_font, false, true);
+    // The satchel slot by slot. Board items and food light up to be clicked;
+    // an armed item keeps its slot highlighted until it's placed.
+    let chip_font = (font_sm * 0.62).max(10.0);
+    for (index, slot) in inventory.slots.iter().enumerate() {
+        let (x, y, size, _) = playing_inventory_slot_rect(layout, index, inventory.slot_count());
+        let Some(item) = slot.item else {
+            draw_rectangle(x, y, size, size, color_u8!(30, 36, 30, 160));
+            draw_rectangle_lines(x, y, size, size, 1.5, color_u8!(90, 100, 90, 200));
+            continue;
+        };
+        let (label, color) = inventory_chip(item);
+        let is_armed = matches!(item, ItemType::BoardModifier(kind) if armed.is_some_and(|armed| armed.kind == kind));
+        draw_inventory_slot(x, y, size, label, slot.count, color, chip_font, is_armed, true);
+    }
+    if let Some(armed) = armed {
+        draw_text(
+            &format!("{}  [Esc] put away", targeting::armed_prompt(armed)),
+            bar_x + bar_width * 0.3,
+            inv_title_y,
+            (font_sm * 0.72).max(11.0),
+            YELLOW,
+        );
     }
 
     let (visit_x, visit_y, visit_w, visit_h) = playing_visit_garden_button_rect(layout);
@@ -1095,6 +1358,113 @@ pub fn draw_playing_ui(
         WHITE,
     );
 
+    // Undo: lit with its fee while the last move can be taken back.
+    let (undo_x, undo_y, undo_w, undo_h) = playing_undo_button_rect(layout);
+    let (undo_fill, undo_edge, undo_text) = if undo_fee.is_some() {
+        (color_u8!(70, 58, 30, 255), color_u8!(240, 210, 130, 255), WHITE)
+    } else {
+        (color_u8!(40, 40, 40, 200), color_u8!(90, 90, 90, 255), color_u8!(120, 120, 120, 255))
+    };
+    draw_rectangle(undo_x, undo_y, undo_w, undo_h, undo_fill);
+    draw_rectangle_lines(undo_x, undo_y, undo_w, undo_h, 3.0, undo_edge);
+    let undo_font = (font_sm * 0.75).max(11.0);
+    let label = "UNDO";
+    let dims = measure_text(label, None, undo_font as u16, 1.0);
+    draw_text(label, undo_x + (undo_w - dims.width) * 0.5, undo_y + undo_h * 0.45, undo_font, undo_text);
+    if let Some(fee) = undo_fee {
+        let fee_text = format!("-{}", fee);
+        let dims = measure_text(&fee_text, None, undo_font as u16, 1.0);
+        draw_text(&fee_text, undo_x + (undo_w - dims.width) * 0.5, undo_y + undo_h * 0.82, undo_font, GOLD);
+    }
+
+    let (_, settings_y, _, settings_h) = playing_descend_button_rect(layout);
+    let strict_text = match (strict_forced, strict_swaps) {
+        (true, _) => "Strict swaps: ON (level)",
+        (false, true) => "[T] Strict swaps: ON",
+        (false, false) => "[T] Strict swaps: OFF",
+    };
+    draw_text(
+        &format!(
+            "[H] Hints: {}   {}   [U] Undo   [Tab] Satchel   [RMB] Sell",
+            if hints_enabled { "ON" } else { "OFF" },
+            strict_text,
+        ),
+        bar_x,
+        settings_y + settings_h + row_h * 0.9,
+        (font_sm * 0.8).max(12.0),
+        color_u8!(180, 200, 180, 220),
+    );
+
+    if !move_score.clears.is_empty() {
+        let multiplier = move_score.max_multiplier();
+        let (tiles, length, groups) = move_score.parts();
+        let mut text = format!("Last move: +{}  ({} tiles", move_score.total(), tiles);
+        if length > 0 {
+            text.push_str(&format!(", +{} long", length));
+        }
+        if groups > 0 {
+            text.push_str(&format!(", +{} combo", groups));
+        }
+        if multiplier > 1 {
+            text.push_str(&format!(", {} cascades up to x{}", move_score.cascades(), multiplier));
+        }
+        if move_score.modifier_bonus > 0 {
+            text.push_str(&format!(", +{} charm", move_score.modifier_bonus));
+        }
+        if move_score.food_bonus > 0 {
+            text.push_str(&format!(", +{} mint", move_score.food_bonus));
+        }
+        text.push(')');
+        draw_text(
+            &text,
+            bar_x,
+            settings_y + settings_h + row_h * 1.75,
+            (font_sm * 0.8).max(12.0),
+            color_u8!(255, 225, 150, 235),
+        );
+    }
+
+    // Level objectives, ticked off in green as they complete.
+    let objectives_y = settings_y + settings_h + row_h * 2.75;
+    for (index, (text, complete)) in objectives.iter().enumerate() {
+        let (mark, color) = if *complete {
+            ("[x]", color_u8!(140, 230, 140, 255))
+        } else {
+            ("[ ]", color_u8!(235, 235, 215, 255))
+        };
+        draw_text(
+            &format!("{} {}", mark, text),
+            bar_x,
+            objectives_y + index as f32 * row_h * 0.85,
+            (font_sm * 0.8).max(12.0),
+            color,
+        );
+    }
+
+    // Active board modifiers as badges, then the ones still in the satchel.
+    let badge_font = (font_sm * 0.7).max(11.0);
+    let badge_h = row_h * 0.7;
+    let badges_y = objectives_y + objectives.len() as f32 * row_h * 0.85;
+    for (index, badge) in modifier_badges.iter().enumerate() {
+        let y = badges_y + index as f32 * (badge_h + row_h * 0.12);
+        let chip_w = badge_h * 1.9;
+        draw_rectangle(bar_x, y, chip_w, badge_h, badge.color);
+        draw_rectangle_lines(bar_x, y, chip_w, badge_h, 2.0, WHITE);
+        let dims = measure_text(badge.label, None, badge_font as u16, 1.0);
+        draw_text(badge.label, bar_x + (chip_w - dims.width) * 0.5, y + badge_h * 0.7, badge_font, WHITE);
+        draw_text(&badge.detail, bar_x + chip_w + row_h * 0.2, y + badge_h * 0.7, badge_font, badge.color);
+    }
+    let carried_y = badges_y + modifier_badges.len() as f32 * (badge_h + row_h * 0.12) + row_h * 0.55;
+    for (index, line) in carried_modifiers.iter().enumerate() {
+        draw_text(
+            line,
+            bar_x,
+            carried_y + index as f32 * row_h * 0.7,
+            badge_font,
+            color_u8!(200, 210, 230, 220),
+        );
+    }
+
     if is_farming {
         let (btn_x, btn_y, btn_w, btn_h) = playing_descend_button_rect(layout);
         draw_rectangle(btn_x, btn_y, btn_w, btn_h, DARKGREEN);
@@ -1109,6 +1479,179 @@ pub fn draw_playing_ui(
     }
 }
 
+/// The satchel laid out to rearrange. A dragged stack follows the mouse.
+pub fn draw_inventory_screen(inventory: &Inventory, drag: Option<usize>, editable: bool) {
+    let sw = screen_width();
+    let sh = screen_height();
+    draw_rectangle(0.0, 0.0, sw, sh, Color::new(0.02, 0.04, 0.02, 0.82));
+    draw_text("SATCHEL", sw * 0.42, sh * 0.18, (sh * 0.07).max(28.0), color_u8!(190, 230, 170, 255));
+
+    let slot_count = inventory.slot_count();
+    let (_, _, slot_size, _) = inventory_screen_slot_rect(0, slot_count);
+    let chip_font = (slot_size * 0.2).max(11.0);
+    let (mx, my) = mouse_position();
+    let mut hovered = None;
+    for (index, slot) in inventory.slots.iter().enumerate() {
+        let (x, y, size, _) = inventory_screen_slot_rect(index, slot_count);
+        if point_in_rect(mx, my, x, y, size, size) {
+            hovered = slot.item.map(|item| (item, slot.count));
+        }
+        match slot.item {
+            Some(item) if drag != Some(index) => {
+                let (label, color) = inventory_chip(item);
+                draw_inventory_slot(x, y, size, label, slot.count, color, chip_font, false, editable);
+            }
+            _ => {
+                draw_rectangle(x, y, size, size, color_u8!(30, 36, 30, 200));
+                draw_rectangle_lines(x, y, size, size, 1.5, color_u8!(90, 110, 90, 220));
+            }
+        }
+    }
+    if let Some(slot) = drag.and_then(|index| inventory.slots.get(index)) {
+        if let Some(item) = slot.item {
+            let (label, color) = inventory_chip(item);
+            let size = slot_size * 1.08;
+            draw_inventory_slot(mx - size * 0.5, my - size * 0.5, size, label, slot.count, color, chip_font, true, true);
+        }
+    }
+
+    let font = (sh * 0.03).max(14.0);
+    if let Some((item, count)) = hovered {
+        draw_text(&format!("{} x{}", economy::item_name(item), count), sw * 0.3, sh * 0.64, font, WHITE);
+    }
+
+    let (sort_x, sort_y, sort_w, sort_h) = inventory_sort_button_rect();
+    draw_rectangle(sort_x, sort_y, sort_w, sort_h, color_u8!(40, 70, 45, 255));
+    draw_rectangle_lines(sort_x, sort_y, sort_w, sort_h, 2.0, color_u8!(150, 220, 150, 255));
+    draw_text("AUTO-SORT", sort_x + sort_w * 0.16, sort_y + sort_h * 0.66, font, WHITE);
+
+    let help = if editable {
+        "Drag to move or merge   Shift-click to split   Right-click to sell   [Tab] Close"
+    } else {
+        "Wait for the board to settle to rearrange   [Tab] Close"
+    };
+    draw_text(help, sw * 0.12, sh * 0.86, (font * 0.85).max(12.0), color_u8!(190, 210, 190, 230));
+}
+
+/// The satchel along the top and the garden shed below it.
+pub fn draw_shed_screen(inventory: &Inventory, shed: &Inventory, overflow_to_shed: bool) {
+    let sw = screen_width();
+    let sh = screen_height();
+    draw_rectangle(0.0, 0.0, sw, sh, Color::new(0.04, 0.03, 0.02, 0.84));
+    let title_font = (sh * 0.04).max(18.0);
+    let title_color = color_u8!(230, 205, 160, 255);
+    draw_text("SATCHEL", sw * 0.075, sh * 0.19, title_font, title_color);
+    draw_text(
+        &format!("SHED {}/{}", economy::inventory_used_slots(shed), shed.slot_count()),
+        sw * 0.075,
+        sh * 0.41,
+        title_font,
+        title_color,
+    );
+
+    let (mx, my) = mouse_position();
+    let mut hovered = None;
+    let bag_rects = (0..inventory.slot_count()).map(|index| shed_bag_slot_rect(index, inventory.slot_count()));
+    let shed_rects = (0..shed.slot_count()).map(shed_slot_rect);
+    let slots = inventory.slots.iter().zip(bag_rects).chain(shed.slots.iter().zip(shed_rects));
+    for (slot, (x, y, size, _)) in slots {
+        if point_in_rect(mx, my, x, y, size, size) {
+            hovered = slot.item.map(|item| (item, slot.count));
+        }
+        match slot.item {
+            Some(item) => {
+                let (label, color) = inventory_chip(item);
+                draw_inventory_slot(x, y, size, label, slot.count, color, (size * 0.2).max(11.0), false, true);
+            }
+            None => {
+                draw_rectangle(x, y, size, size, color_u8!(36, 30, 24, 200));
+                draw_rectangle_lines(x, y, size, size, 1.5, color_u8!(110, 95, 75, 220));
+            }
+        }
+    }
+
+    let font = (sh * 0.03).max(14.0);
+    if let Some((item, count)) = hovered {
+        draw_text(&format!("{} x{}", economy::item_name(item), count), sw * 0.3, sh * 0.38, font, WHITE);
+    }
+    let overflow = if overflow_to_shed { "shed, then Bagira" } else { "Bagira" };
+    draw_text(
+        &format!("Board drops that don't fit the satchel go to: {}   [O] Change", overflow),
+        sw * 0.12,
+        sh * 0.82,
+        font,
+        color_u8!(240, 215, 150, 255),
+    );
+    draw_text(
+        "Click a stack to move it across   [S] Close",
+        sw * 0.12,
+        sh * 0.88,
+        (font * 0.85).max(12.0),
+        color_u8!(210, 200, 180, 230),
+    );
+}
+
+/// Bagira's reply to a sale, along the bottom of the screen.
+pub fn draw_discard_note(text: &str, alpha: f32) {
+    let sw = screen_width();
+    let sh = screen_height();
+    let font = (sh * 0.03).max(14.0);
+    let dims = measure_text(text, None, font as u16, 1.0);
+    let x = (sw - dims.width) * 0.5;
+    let y = sh * 0.8;
+    draw_rectangle(x - font * 0.6, y - font * 1.1, dims.width + font * 1.2, font * 1.6, Color::new(0.1, 0.06, 0.14, 0.85 * alpha));
+    draw_text(text, x, y, font, Color::new(0.92, 0.82, 1.0, alpha));
+}
+
+/// Asks before selling something valuable.
+pub fn draw_discard_confirm(item_name: &str, paid: i32) {
+    let sw = screen_width();
+    let sh = screen_height();
+    draw_rectangle(0.0, 0.0, sw, sh, Color::new(0.0, 0.0, 0.0, 0.5));
+    let (box_w, box_h) = (sw * 0.56, sh * 0.3);
+    let (box_x, box_y) = ((sw - box_w) * 0.5, (sh - box_h) * 0.5);
+    draw_rectangle(box_x, box_y, box_w, box_h, color_u8!(36, 26, 46, 240));
+    draw_rectangle_lines(box_x, box_y, box_w, box_h, 3.0, color_u8!(190, 150, 230, 255));
+    let font = (sh * 0.036).max(16.0);
+    let pad = box_w * 0.05;
+    draw_text(&format!("Sell {} to Bagira for {} Leaves?", item_name, paid), box_x + pad, box_y + box_h * 0.3, font, WHITE);
+    draw_text(
+        &format!("\"{}\"", discard::BAGIRA_DISCARD_LINE),
+        box_x + pad,
+        box_y + box_h * 0.55,
+        (font * 0.8).max(13.0),
+        color_u8!(220, 200, 245, 255),
+    );
+    draw_text("[Y] Sell   [N] Keep", box_x + pad, box_y + box_h * 0.82, font, GOLD);
+}
+
+pub fn draw_level_failed_ui(reason: &str) {
+    let sw = screen_width();
+    let sh = screen_height();
+    draw_rectangle(0.0, 0.0, sw, sh, Color::new(0.0, 0.0, 0.0, 0.55));
+    draw_text(
+        reason,
+        sw * 0.19,
+        sh * 0.43,
+        (sh * 0.103).max(36.0),
+        color_u8!(255, 110, 90, 255),
+    );
+    draw_text(
+        "[ENTER] Try Again",
+        sw * 0.15,
+        sh * 0.55,
+        (sh * 0.052).max(22.0),
+        WHITE,
+    );
+    draw_text(
+        "Your satchel is safe - only this level's score is lost.",
+        sw * 0.15,
+        sh * 0.62,
+        (sh * 0.036).max(16.0),
+        GOLD,
+    );
+}
+
 pub fn draw_level_transition_ui() {
     let sw = screen_width();
     let sh = screen_height();
@@ -0,0 +1,610 @@
+// --- Board session recording & replay ---
+//
+// A replay file is plain text: a header with the seed, the RNG stream states
+// and the starting board, then one line per player action:
+//
+//     lucid_leaves_replay 3
+//     seed 1234
+//     clock <unix seconds when recording began>
+//     rng <board> <drops> <cosmetic>
+//     board <width> <height>
+//     grid <tile tokens, column by column; ## marks a hole, a trailing x a blocker>
+//     <frame> <phase> <action> [args...]
+//
+// Actions are recorded where input enters the game and replayed through the
+// same handlers. An action waits until the game is back in the phase it was
+// recorded in and the board is idle, so replays don't depend on frame timing.
+// While recording or replaying, time itself comes from the frame count: every
+// update advances by a fixed step, and the garden's clock starts at the
+// header's `clock` and moves one second per REPLAY_FPS frames.
+
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::Write;
+
+use macroquad::prelude::get_frame_time;
+
+use crate::board::{Board, BoardShape};
+use crate::game_state::{GamePhase, GameState};
+use crate::inventory::{BoardModifierKind, FoodBuffKind};
+use crate::rng::RngStream;
+use crate::tile::{SpecialGem, Tile, TileType};
+use crate::upgrades::BagiraUpgrade;
+
+pub const REPLAY_VERSION: u32 = 3;
+pub const REPLAY_FPS: u64 = 60;
+
+/// Transitions between screens, applied by `GameState::apply_phase_action`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PhaseAction {
+    VisitGarden,        // Playing -> Garden
+    DescendFromFarming, // Playing (farming) -> LevelTransition
+    Descend,            // LevelTransition -> next level or Shop
+    Farm,               // LevelTransition -> Playing, farming
+    ShopSpend,          // spend 500 at the shrine
+    LeaveShop,          // Shop -> next biome
+    ReturnToPuzzle,     // Garden -> Playing
+    GoHunt,             // Garden -> Hunt
+    LeaveHunt,          // Hunt -> Garden
+    RetryLevel,         // LevelFailed -> Playing, same level
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ReplayAction {
+    Select { x: usize, y: usize },
+    Deselect,
+    GardenClick { nx: f32, ny: f32 }, // normalised screen position
+    GardenEscape,
+    GardenToggleDrawer,
+    ToggleStrictSwaps,
+    Undo,
+    UseModifier(BoardModifierKind),
+    EatFood(FoodBuffKind),
+    ArmItem(BoardModifierKind),
+    Disarm,
+    Discard { slot: usize },
+    InventoryMove { from: usize, to: usize },
+    InventorySplit { slot: usize },
+    InventorySort,
+    BuyUpgrade(BagiraUpgrade),
+    ShedStore { slot: usize },
+    ShedTake { slot: usize },
+    ToggleShedOverflow,
+    Phase(PhaseAction),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ReplayEntry {
+    pub frame: u64,
+    pub phase: GamePhase,
+    pub action: ReplayAction,
+}
+
+/// State captured when recording starts and restored before playback.
+pub struct ReplayHeader {
+    pub seed: u64,
+    pub clock: i64, // unix seconds at frame 0
+    pub rng_states: [u64; 3],
+    pub grid: Board,
+}
+
+pub struct ReplayRecorder {
+    path: String,
+}
+
+pub struct ReplayPlayer {
+    pub entries: VecDeque<ReplayEntry>,
+}
+
+fn phase_tag(phase: GamePhase) -> &'static str {
+    match phase {
+        GamePhase::Playing => "playing",
+        GamePhase::LevelTransition => "transition",
+        GamePhase::LevelFailed => "failed",
+        GamePhase::Shop => "shop",
+        GamePhase::Garden => "garden",
+        GamePhase::Hunt => "hunt",
+        GamePhase::BossHunt => "boss_hunt",
+    }
+}
+
+fn parse_phase(tag: &str) -> Option<GamePhase> {
+    Some(match tag {
+        "playing" => GamePhase::Playing,
+        "transition" => GamePhase::LevelTransition,
+        "failed" => GamePhase::LevelFailed,
+        "shop" => GamePhase::Shop,
+        "garden" => GamePhase::Garden,
+        "hunt" => GamePhase::Hunt,
+        "boss_hunt" => GamePhase::BossHunt,
+        _ => return None,
+    })
+}
+
+const PHASE_ACTION_TAGS: [(PhaseAction, &str); 10] = [
+    (PhaseAction::VisitGarden, "visit_garden"),
+    (PhaseAction::DescendFromFarming, "descend_farming"),
+    (PhaseAction::Descend, "descend"),
+    (PhaseAction::Farm, "farm"),
+    (PhaseAction::ShopSpend, "shop_spend"),
+    (PhaseAction::LeaveShop, "leave_shop"),
+    (PhaseAction::ReturnToPuzzle, "return_to_puzzle"),
+    (PhaseAction::GoHunt, "go_hunt"),
+    (PhaseAction::LeaveHunt, "leave_hunt"),
+    (PhaseAction::RetryLevel, "retry_level"),
+];
+
+const MODIFIER_TAGS: [(BoardModifierKind, &str); 7] = [
+    (BoardModifierKind::TileWeightShift, "tile_weight_shift"),
+    (BoardModifierKind::IllegalCostCap, "illegal_cost_cap"),
+    (BoardModifierKind::CascadeBonus, "cascade_bonus"),
+    (BoardModifierKind::FogClear, "fog_clear"),
+    (BoardModifierKind::TileRemove, "tile_remove"),
+    (BoardModifierKind::FreeSwap, "free_swap"),
+    (BoardModifierKind::RowClear, "row_clear"),
+];
+
+fn modifier_tag(kind: BoardModifierKind) -> &'static str {
+    MODIFIER_TAGS.iter().find(|(candidate, _)| *candidate == kind).map_or("", |(_, tag)| tag)
+}
+
+fn parse_modifier(tag: &str) -> Option<BoardModifierKind> {
+    MODIFIER_TAGS.iter().find(|(_, candidate)| *candidate == tag).map(|(kind, _)| *kind)
+}
+
+const FOOD_TAGS: [(FoodBuffKind, &str); 3] = [
+    (FoodBuffKind::MintLeaf, "mint_leaf"),
+    (FoodBuffKind::HoneyBread, "honey_bread"),
+    (FoodBuffKind::SpicedNut, "spiced_nut"),
+];
+
+fn food_tag(kind: FoodBuffKind) -> &'static str {
+    FOOD_TAGS.iter().find(|(candidate, _)| *candidate == kind).map_or("", |(_, tag)| tag)
+}
+
+fn parse_food(tag: &str) -> Option<FoodBuffKind> {
+    FOOD_TAGS.iter().find(|(_, candidate)| *candidate == tag).map(|(kind, _)| *kind)
+}
+
+const UPGRADE_TAGS: [(BagiraUpgrade, &str); 2] = [
+    (BagiraUpgrade::BiggerSatchel, "bigger_satchel"),
+    (BagiraUpgrade::DeeperPockets, "deeper_pockets"),
+];
+
+fn upgrade_tag(upgrade: BagiraUpgrade) -> &'static str {
+    UPGRADE_TAGS.iter().find(|(candidate, _)| *candidate == upgrade).map_or("", |(_, tag)| tag)
+}
+
+fn parse_upgrade(tag: &str) -> Option<BagiraUpgrade> {
+    UPGRADE_TAGS.iter().find(|(_, candidate)| *candidate == tag).map(|(upgrade, _)| *upgrade)
+}
+
+fn action_to_line(action: ReplayAction) -> String {
+    match action {
+        ReplayAction::Select { x, y } => format!("select {} {}", x, y),
+        ReplayAction::Deselect => "deselect".to_owned(),
+        ReplayAction::GardenClick { nx, ny } => format!("garden_click {} {}", nx, ny),
+        ReplayAction::GardenEscape => "garden_escape".to_owned(),
+        ReplayAction::GardenToggleDrawer => "garden_drawer".to_owned(),
+        ReplayAction::ToggleStrictSwaps => "strict_swaps".to_owned(),
+        ReplayAction::Undo => "undo".to_owned(),
+        ReplayAction::UseModifier(kind) => format!("use_modifier {}", modifier_tag(kind)),
+        ReplayAction::EatFood(kind) => format!("eat {}", food_tag(kind)),
+        ReplayAction::ArmItem(kind) => format!("arm {}", modifier_tag(kind)),
+        ReplayAction::Disarm => "disarm".to_owned(),
+        ReplayAction::Discard { slot } => format!("discard {}", slot),
+        ReplayAction::InventoryMove { from, to } => format!("inventory_move {} {}", from, to),
+        ReplayAction::InventorySplit { slot } => format!("inventory_split {}", slot),
+        ReplayAction::InventorySort => "inventory_sort".to_owned(),
+        ReplayAction::BuyUpgrade(upgrade) => format!("buy_upgrade {}", upgrade_tag(upgrade)),
+        ReplayAction::ShedStore { slot } => format!("shed_store {}", slot),
+        ReplayAction::ShedTake { slot } => format!("shed_take {}", slot),
+        ReplayAction::ToggleShedOverflow => "shed_overflow".to_owned(),
+        ReplayAction::Phase(phase_action) => PHASE_ACTION_TAGS
+            .iter()
+            .find(|(candidate, _)| *candidate == phase_action)
+            .map(|(_, tag)| (*tag).to_owned())
+            .unwrap_or_default(),
+    }
+}
+
+fn parse_action(words: &[&str]) -> Option<ReplayAction> {
+    let num = |index: usize| words.get(index).and_then(|word| word.parse::<usize>().ok());
+    let float = |index: usize| words.get(index).and_then(|word| word.parse::<f32>().ok());
+    Some(match *words.first()? {
+        "select" => ReplayAction::Select { x: num(1)?, y: num(2)? },
+        "deselect" => ReplayAction::Deselect,
+        "garden_click" => ReplayAction::GardenClick { nx: float(1)?, ny: float(2)? },
+        "garden_escape" => ReplayAction::GardenEscape,
+        "garden_drawer" => ReplayAction::GardenToggleDrawer,
+        "strict_swaps" => ReplayAction::ToggleStrictSwaps,
+        "undo" => ReplayAction::Undo,
+        "use_modifier" => ReplayAction::UseModifier(parse_modifier(words.get(1)?)?),
+        "eat" => ReplayAction::EatFood(parse_food(words.get(1)?)?),
+        "arm" => ReplayAction::ArmItem(parse_modifier(words.get(1)?)?),
+        "disarm" => ReplayAction::Disarm,
+        "discard" => ReplayAction::Discard { slot: num(1)? },
+        "inventory_move" => ReplayAction::InventoryMove { from: num(1)?, to: num(2)? },
+        "inventory_split" => ReplayAction::InventorySplit { slot: num(1)? },
+        "inventory_sort" => ReplayAction::InventorySort,
+        "buy_upgrade" => ReplayAction::BuyUpgrade(parse_upgrade(words.get(1)?)?),
+        "shed_store" => ReplayAction::ShedStore { slot: num(1)? },
+        "shed_take" => ReplayAction::ShedTake { slot: num(1)? },
+        "shed_overflow" => ReplayAction::ToggleShedOverflow,
+        tag => ReplayAction::Phase(
+            PHASE_ACTION_TAGS.iter().find(|(_, candidate)| *candidate == tag)?.0,
+        ),
+    })
+}
+
+fn tile_token(tile: &Tile) -> String {
+    let kind = match tile.kind {
+        TileType::Sun => 'S',
+        TileType::Moon => 'M',
+        TileType::Water => 'W',
+        TileType::Leaf => 'L',
+        TileType::Exotic => 'E',
+        TileType::Ingredient => 'I',
+        TileType::Empty => '.',
+    };
+    let special = match tile.special {
+        None => '-',
+        Some(SpecialGem::LineHorizontal) => 'h',
+        Some(SpecialGem::LineVertical) => 'v',
+        Some(SpecialGem::Bomb) => 'b',
+        Some(SpecialGem::ColorClear) => 'c',
+    };
+    let blocker = if tile.blocker { "x" } else { "" };
+    format!("{}{}{}", kind, special, blocker)
+}
+
+const HOLE_TOKEN: &str = "##";
+
+fn parse_tile(token: &str) -> Option<Tile> {
+    let mut chars = token.chars();
+    let kind = match chars.next()? {
+        'S' => TileType::Sun,
+        'M' => TileType::Moon,
+        'W' => TileType::Water,
+        'L' => TileType::Leaf,
+        'E' => TileType::Exotic,
+        'I' => TileType::Ingredient,
+        '.' => TileType::Empty,
+        _ => return None,
+    };
+    let special = match chars.next()? {
+        '-' => None,
+        'h' => Some(SpecialGem::LineHorizontal),
+        'v' => Some(SpecialGem::LineVertical),
+        'b' => Some(SpecialGem::Bomb),
+        'c' => Some(SpecialGem::ColorClear),
+        _ => return None,
+    };
+    let blocker = match chars.next() {
+        None => false,
+        Some('x') => true,
+        Some(_) => return None,
+    };
+    Some(Tile { kind, special, blocker, ..Tile::EMPTY })
+}
+
+pub(crate) fn unix_now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+impl ReplayRecorder {
+    /// Start a new replay file, writing the header immediately.
+    pub fn create(path: &str, header: &ReplayHeader) -> std::io::Result<Self> {
+        let grid = &header.grid;
+        let tiles: Vec<String> = (0..grid.width())
+            .flat_map(|x| (0..grid.height()).map(move |y| (x, y)))
+            .map(|(x, y)| if grid.is_playable(x, y) { tile_token(&grid[x][y]) } else { HOLE_TOKEN.to_owned() })
+            .collect();
+        let data = format!(
+            "lucid_leaves_replay {}\nseed {}\nclock {}\nrng {} {} {}\nboard {} {}\ngrid {}\n",
+            REPLAY_VERSION,
+            header.seed,
+            header.clock,
+            header.rng_states[0],
+            header.rng_states[1],
+            header.rng_states[2],
+            grid.width(),
+            grid.height(),
+            tiles.join(" "),
+        );
+        std::fs::write(path, data)?;
+        Ok(Self { path: path.to_owned() })
+    }
+
+    /// Append one action. Written straight away so a crash keeps the log.
+    pub fn record(&self, entry: &ReplayEntry) {
+        let line = format!("{} {} {}\n", entry.frame, phase_tag(entry.phase), action_to_line(entry.action));
+        if let Ok(mut file) = OpenOptions::new().append(true).open(&self.path) {
+            let _ = file.write_all(line.as_bytes());
+        }
+    }
+}
+
+/// Parse a replay into its header and action queue. Errors name the line.
+pub fn parse_replay(data: &str) -> Result<(ReplayHeader, ReplayPlayer), String> {
+    let mut lines = data.lines().enumerate();
+    // The next header line with `prefix` stripped.
+    let mut header_line = |prefix: &str| match lines.next() {
+        Some((index, line)) => line
+            .strip_prefix(prefix)
+            .map(|rest| (index, rest))
+            .ok_or(format!("line {}: expected '{}'", index + 1, prefix.trim())),
+        None => Err(format!("missing '{}' line", prefix.trim())),
+    };
+    let fail = |index: usize, message: &str| format!("line {}: {}", index + 1, message);
+
+    let (index, rest) = header_line("lucid_leaves_replay ")?;
+    let version = rest.parse::<u32>().map_err(|_| fail(index, "bad version"))?;
+    if version != REPLAY_VERSION {
+        return Err(fail(index, &format!("version {} is not {}", version, REPLAY_VERSION)));
+    }
+    let (index, rest) = header_line("seed ")?;
+    let seed = rest.parse::<u64>().map_err(|_| fail(index, "bad seed"))?;
+    let (index, rest) = header_line("clock ")?;
+    let clock = rest.parse::<i64>().map_err(|_| fail(index, "bad clock"))?;
+
+    let (index, rest) = header_line("rng ")?;
+    let rng_words: Vec<u64> = rest.split_whitespace().filter_map(|word| word.parse::<u64>().ok()).collect();
+    let rng_states: [u64; 3] = rng_words.try_into().map_err(|_| fail(index, "expected three RNG states"))?;
+
+    let (index, rest) = header_line("board ")?;
+    let size: Vec<usize> = rest.split_whitespace().filter_map(|word| word.parse::<usize>().ok()).collect();
+    let [width, height]: [usize; 2] = size.try_into().map_err(|_| fail(index, "expected a width and height"))?;
+
+    // None marks a hole.
+    let (index, rest) = header_line("grid ")?;
+    let tiles: Vec<Option<Tile>> = rest
+        .split_whitespace()
+        .map(|token| if token == HOLE_TOKEN { Some(None) } else { parse_tile(token).map(Some) })
+        .collect::<Option<Vec<Option<Tile>>>>()
+        .ok_or(fail(index, "bad tile token"))?;
+    if width == 0 || height == 0 || tiles.len() != width * height {
+        return Err(fail(index, "grid does not match the board size"));
+    }
+    let mut shape = BoardShape::rect(width, height);
+    for (index, tile) in tiles.iter().enumerate() {
+        shape.mask[index / height][index % height] = tile.is_some();
+    }
+    let mut grid = Board::new(shape);
+    for (index, tile) in tiles.into_iter().enumerate() {
+        grid[index / height][index % height] = tile.unwrap_or(Tile::EMPTY);
+    }
+
+    let mut entries = VecDeque::new();
+    for (index, line) in lines {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if words.is_empty() {
+            continue;
+        }
+        let (Some(frame), Some(phase), Some(action)) = (
+            words[0].parse::<u64>().ok(),
+            words.get(1).and_then(|tag| parse_phase(tag)),
+            words.get(2..).and_then(parse_action),
+        ) else {
+            return Err(fail(index, &format!("bad action '{}'", line.trim())));
+        };
+        entries.push_back(ReplayEntry { frame, phase, action });
+    }
+
+    Ok((ReplayHeader { seed, clock, rng_states, grid }, ReplayPlayer { entries }))
+}
+
+/// Read a replay file into its header and action queue.
+pub fn load_replay(path: &str) -> Result<(ReplayHeader, ReplayPlayer), String> {
+    let data = std::fs::read_to_string(path).map_err(|error| error.to_string())?;
+    parse_replay(&data)
+}
+
+impl GameState {
+    pub fn start_recording(&mut self, path: &str) -> std::io::Result<()> {
+        let header = ReplayHeader {
+            seed: self.rng.seed,
+            clock: unix_now() - (self.frame / REPLAY_FPS) as i64,
+            rng_states: [self.rng.board.state(), self.rng.drops.state(), self.rng.cosmetic.state()],
+            grid: self.grid.clone(),
+        };
+        self.recorder = Some(ReplayRecorder::create(path, &header)?);
+        self.replay_clock = Some(header.clock);
+        Ok(())
+    }
+
+    /// Restore the recorded starting state and queue its actions.
+    pub fn start_replay(&mut self, header: ReplayHeader, player: ReplayPlayer) {
+        self.rng.seed = header.seed;
+        self.replay_clock = Some(header.clock);
+        self.rng.board = RngStream::from_state(header.rng_states[0]);
+        self.rng.drops = RngStream::from_state(header.rng_states[1]);
+        self.rng.cosmetic = RngStream::from_state(header.rng_states[2]);
+        // The file holds tiles only; the level's spawn table carries over.
+        let spawn = self.grid.spawn_table().clone();
+        self.grid = header.grid;
+        *self.grid.spawn_table_mut() = spawn;
+        self.replay = Some(player);
+    }
+
+    /// Seconds since the epoch, as the garden sees them. Counted in frames
+    /// once a recording or replay has begun, so both see the same times.
+    pub(crate) fn clock_now(&self) -> i64 {
+        match self.replay_clock {
+            Some(clock) => clock + (self.frame / REPLAY_FPS) as i64,
+            None => unix_now(),
+        }
+    }
+
+    /// Seconds this update covers: a fixed step while recording or
+    /// replaying, the real frame time otherwise.
+    pub(crate) fn frame_delta(&self) -> f32 {
+        if self.recorder.is_some() || self.replay.is_some() {
+            1.0 / REPLAY_FPS as f32
+        } else {
+            get_frame_time()
+        }
+    }
+
+    /// Live input is ignored while a replay drives the game.
+    pub(crate) fn accepts_live_input(&self) -> bool {
+        self.replay.is_none()
+    }
+
+    /// Record a live action, then apply it.
+    pub(crate) fn perform_action(&mut self, action: ReplayAction) {
+        if let Some(recorder) = &self.recorder {
+            recorder.record(&ReplayEntry { frame: self.frame, phase: self.phase, action });
+        }
+        self.apply_action(action);
+    }
+
+    pub(crate) fn apply_action(&mut self, action: ReplayAction) {
+        match action {
+            ReplayAction::Select { x, y } => self.handle_board_selection(x, y),
+            ReplayAction::Deselect => self.selected = None,
+            ReplayAction::GardenClick { nx, ny } => {
+                let (sw, sh) = (macroquad::window::screen_width(), macroquad::window::screen_height());
+                self.handle_garden_click(nx * sw, ny * sh);
+            }
+            ReplayAction::GardenEscape => self.handle_garden_escape(),
+            ReplayAction::GardenToggleDrawer => self.toggle_garden_drawer(),
+            ReplayAction::ToggleStrictSwaps => self.settings.strict_swaps = !self.settings.strict_swaps,
+            ReplayAction::Undo => self.undo_last_move(),
+            ReplayAction::UseModifier(kind) => {
+                self.use_board_modifier(kind);
+            }
+            ReplayAction::EatFood(kind) => {
+                self.eat_food(kind);
+            }
+            ReplayAction::ArmItem(kind) => self.arm_item(kind),
+            ReplayAction::Disarm => self.disarm_item(),
+            ReplayAction::Discard { slot } => self.discard_slot(slot),
+            ReplayAction::InventoryMove { from, to } => self.move_inventory_slot(from, to),
+            ReplayAction::InventorySplit { slot } => self.split_inventory_slot(slot),
+            ReplayAction::InventorySort => self.sort_inventory(),
+            ReplayAction::BuyUpgrade(upgrade) => self.buy_upgrade(upgrade),
+            ReplayAction::ShedStore { slot } => self.store_in_shed(slot),
+            ReplayAction::ShedTake { slot } => self.take_from_shed(slot),
+            ReplayAction::ToggleShedOverflow => self.settings.overflow_to_shed = !self.settings.overflow_to_shed,
+            ReplayAction::Phase(phase_action) => self.apply_phase_action(phase_action),
+        }
+    }
+
+    /// Feed due replay actions back in, each once its phase is current and the
+    /// board has settled.
+    pub(crate) fn update_replay(&mut self) {
+        loop {
+            let Some(entry) = self.replay.as_ref().and_then(|player| player.entries.front().copied()) else {
+                // Out of actions: hand control back to the player.
+                self.replay = None;
+                return;
+            };
+            let board_busy = self.is_clearing() || self.is_swapping() || self.is_settling() || self.is_shuffling();
+            if entry.frame > self.frame || entry.phase != self.phase || board_busy {
+                return;
+            }
+            if let Some(player) = self.replay.as_mut() {
+                player.entries.pop_front();
+            }
+            self.apply_action(entry.action);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(action: ReplayAction) -> Option<ReplayAction> {
+        let line = action_to_line(action);
+        let words: Vec<&str> = line.split_whitespace().collect();
+        parse_action(&words)
+    }
+
+    #[test]
+    fn every_action_survives_a_round_trip() {
+        let mut actions = vec![
+            ReplayAction::Select { x: 3, y: 7 },
+            ReplayAction::Deselect,
+            ReplayAction::GardenClick { nx: 0.125, ny: 0.8765432 },
+            ReplayAction::GardenEscape,
+            ReplayAction::GardenToggleDrawer,
+            ReplayAction::ToggleStrictSwaps,
+            ReplayAction::Undo,
+            ReplayAction::Disarm,
+            ReplayAction::Discard { slot: 5 },
+            ReplayAction::InventoryMove { from: 0, to: 11 },
+            ReplayAction::InventorySplit { slot: 2 },
+            ReplayAction::InventorySort,
+            ReplayAction::ShedStore { slot: 1 },
+            ReplayAction::ShedTake { slot: 23 },
+            ReplayAction::ToggleShedOverflow,
+        ];
+        for (kind, _) in MODIFIER_TAGS {
+            actions.push(ReplayAction::UseModifier(kind));
+            actions.push(ReplayAction::ArmItem(kind));
+        }
+        actions.extend(FOOD_TAGS.map(|(kind, _)| ReplayAction::EatFood(kind)));
+        actions.extend(UPGRADE_TAGS.map(|(upgrade, _)| ReplayAction::BuyUpgrade(upgrade)));
+        actions.extend(PHASE_ACTION_TAGS.map(|(phase_action, _)| ReplayAction::Phase(phase_action)));
+
+        for action in actions {
+            assert_eq!(round_trip(action), Some(action), "line: {}", action_to_line(action));
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        for line in ["", "select 3", "select a b", "use_modifier", "use_modifier teleport", "eat cake", "discard -1", "buy_upgrade", "dance"] {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            assert_eq!(parse_action(&words), None, "line: {:?}", line);
+        }
+    }
+
+    const HEADER: &str = "lucid_leaves_replay 3\nseed 7\nclock 1700000000\nrng 1 2 3\nboard 2 2\ngrid S- M- ## Wbx\n";
+
+    #[test]
+    fn parses_a_replay() {
+        let data = format!("{}12 playing select 0 1\n\n40 garden garden_escape\n", HEADER);
+        let (header, player) = parse_replay(&data).expect("replay should parse");
+        assert_eq!((header.seed, header.clock, header.rng_states), (7, 1_700_000_000, [1, 2, 3]));
+        assert!(!header.grid.is_playable(1, 0));
+        assert!(header.grid[1][1].blocker);
+        let actions: Vec<_> = player.entries.iter().map(|entry| (entry.frame, entry.phase, entry.action)).collect();
+        assert_eq!(
+            actions,
+            [
+                (12, GamePhase::Playing, ReplayAction::Select { x: 0, y: 1 }),
+                (40, GamePhase::Garden, ReplayAction::GardenEscape),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_the_bad_line() {
+        let bad_action = format!("{}12 playing select 0 1\n13 playing dance\n", HEADER);
+        assert_eq!(parse_replay(&bad_action).err().as_deref(), Some("line 8: bad action '13 playing dance'"));
+        let bad_phase = format!("{}12 lobby undo\n", HEADER);
+        assert_eq!(parse_replay(&bad_phase).err().as_deref(), Some("line 7: bad action '12 lobby undo'"));
+        let old_version = HEADER.replacen("replay 3", "replay 2", 1);
+        assert_eq!(parse_replay(&old_version).err().as_deref(), Some("line 1: version 2 is not 3"));
+        let small_grid = HEADER.replacen(" Wbx", "", 1);
+        assert_eq!(parse_replay(&small_grid).err().as_deref(), Some("line 6: grid does not match the board size"));
+        assert_eq!(parse_replay("lucid_leaves_replay 3\nseed 7\n").err().as_deref(), Some("missing 'clock' line"));
+    }
+
+    #[test]
+    fn phases_and_tiles_survive_a_round_trip() {
+        for phase in [GamePhase::Playing, GamePhase::LevelTransition, GamePhase::Shop, GamePhase::Garden, GamePhase::BossHunt] {
+            assert_eq!(parse_phase(phase_tag(phase)), Some(phase));
+        }
+        let tile = Tile { kind: TileType::Moon, special: Some(SpecialGem::Bomb), blocker: true, ..Tile::EMPTY };
+        let parsed = parse_tile(&tile_token(&tile)).expect("tile token should parse");
+        assert_eq!((parsed.kind, parsed.special, parsed.blocker), (tile.kind, tile.special, tile.blocker));
+        assert!(parse_tile("Q-").is_none());
+    }
+}
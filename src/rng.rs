@@ -0,0 +1,74 @@
+// --- Deterministic random numbers ---
+//
+// Everything random in a board session draws from a GameRng owned by
+// GameState, so one seed reproduces the same boards and drops. Each concern
+// gets its own stream: extra particles on a faster machine must never shift
+// which tile spawns next.
+
+const STREAM_BOARD: u64 = 0x6A09_E667_F3BC_C908;
+const STREAM_DROPS: u64 = 0xBB67_AE85_84CA_A73B;
+const STREAM_COSMETIC: u64 = 0x3C6E_F372_FE94_F82B;
+
+/// One SplitMix64 stream. Tiny, fast and its whole state is a single u64.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct RngStream {
+    state: u64,
+}
+
+impl RngStream {
+    pub fn from_state(state: u64) -> Self {
+        Self { state }
+    }
+
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in [0.0, 1.0).
+    pub fn gen_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Uniform in [low, high).
+    pub fn gen_range(&mut self, low: usize, high: usize) -> usize {
+        if high <= low {
+            return low;
+        }
+        low + (self.next_u64() % (high - low) as u64) as usize
+    }
+
+    /// Uniform in [low, high).
+    pub fn gen_range_f32(&mut self, low: f32, high: f32) -> f32 {
+        low + (high - low) * self.gen_f32()
+    }
+}
+
+/// All random streams for one game, derived from a single seed.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct GameRng {
+    pub seed: u64,
+    pub board: RngStream,    // tile spawns, shuffles, board effects
+    pub drops: RngStream,    // resource drop rolls
+    pub cosmetic: RngStream, // particles and other visuals only
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        let mut mixer = RngStream::from_state(seed);
+        let base = mixer.next_u64();
+        Self {
+            seed,
+            board: RngStream::from_state(base ^ STREAM_BOARD),
+            drops: RngStream::from_state(base ^ STREAM_DROPS),
+            cosmetic: RngStream::from_state(base ^ STREAM_COSMETIC),
+        }
+    }
+}
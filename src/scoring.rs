@@ -0,0 +1,77 @@
+// --- Combo scoring ---
+//
+// Every clear is scored from its tiles, then bonuses for long runs and for
+// several groups going at once, and finally multiplied by how deep in the
+// cascade it happened. A move's clears are collected in a ScoreBreakdown so
+// the HUD can show where the points came from.
+
+use crate::match_logic::{self, MatchCell, MatchGroup};
+
+pub const LENGTH_BONUS_PER_TILE: i32 = 20; // each tile past 3 in a group's longest run
+pub const MULTI_GROUP_BONUS: i32 = 30;     // each group past the first in one clear
+pub const CHAIN_MULTIPLIER_MAX: i32 = 5;
+
+/// Points for one clear within a move.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ClearScore {
+    pub chain: u32, // 0 for the clear the swap made, 1+ for cascades
+    pub base: i32,
+    pub length_bonus: i32,
+    pub group_bonus: i32,
+    pub multiplier: i32,
+    pub total: i32,
+}
+
+/// Everything one move scored, clear by clear.
+#[derive(Clone, Debug, Default)]
+pub struct ScoreBreakdown {
+    pub clears: Vec<ClearScore>,
+    pub exotic_bonus: i32,   // Rose-set Exotic blooms
+    pub modifier_bonus: i32, // Cascade Charm payouts
+    pub food_bonus: i32,     // Mint Leaf
+}
+
+impl ScoreBreakdown {
+    pub fn total(&self) -> i32 {
+        self.clears.iter().map(|clear| clear.total).sum::<i32>() + self.exotic_bonus + self.modifier_bonus + self.food_bonus
+    }
+
+    pub fn cascades(&self) -> u32 {
+        self.clears.iter().map(|clear| clear.chain).max().unwrap_or(0)
+    }
+
+    pub fn max_multiplier(&self) -> i32 {
+        self.clears.iter().map(|clear| clear.multiplier).max().unwrap_or(1)
+    }
+
+    /// Unmultiplied points by source: tiles, long runs, extra groups.
+    pub fn parts(&self) -> (i32, i32, i32) {
+        self.clears.iter().fold((0, 0, 0), |(base, length, groups), clear| {
+            (base + clear.base, length + clear.length_bonus, groups + clear.group_bonus)
+        })
+    }
+}
+
+/// x1 for the swap's own clear, then one more per cascade step, capped.
+pub fn chain_multiplier(chain: u32) -> i32 {
+    (chain as i32 + 1).min(CHAIN_MULTIPLIER_MAX)
+}
+
+pub fn score_clear(groups: &[MatchGroup], cleared: &[MatchCell], chain: u32) -> ClearScore {
+    let base = match_logic::score_for_clear(groups, cleared);
+    let length_bonus = groups
+        .iter()
+        .map(|group| group.length.saturating_sub(3) as i32 * LENGTH_BONUS_PER_TILE)
+        .sum();
+    let group_bonus = groups.len().saturating_sub(1) as i32 * MULTI_GROUP_BONUS;
+    let multiplier = chain_multiplier(chain);
+
+    ClearScore {
+        chain,
+        base,
+        length_bonus,
+        group_bonus,
+        multiplier,
+        total: (base + length_bonus + group_bonus) * multiplier,
+    }
+}
@@ -0,0 +1,17 @@
+/// Player-facing toggles. Kept separate from game progress so they survive
+/// board resets and level changes.
+pub struct Settings {
+    pub hints_enabled: bool, // pulse a suggested swap after a few idle seconds
+    pub strict_swaps: bool,  // swaps that make no match slide back for free
+    pub overflow_to_shed: bool, // drops that don't fit the satchel go to the shed, not Bagira
+}
+
+impl Settings {
+    pub fn new() -> Self {
+        Self {
+            hints_enabled: true,
+            strict_swaps: false,
+            overflow_to_shed: false,
+        }
+    }
+}
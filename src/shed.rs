@@ -0,0 +1,62 @@
+// --- Garden storage shed ---
+//
+// A bigger second container that only opens in the garden. Click a stack in
+// the satchel row to put it away, or one in the shed to take it back; as
+// much as fits moves. With overflow routed to the shed, board drops that
+// don't fit the satchel land here while it has room instead of going to
+// Bagira.
+
+use macroquad::prelude::*;
+
+use crate::game_state::{GamePhase, GameState};
+use crate::render;
+use crate::replay::ReplayAction;
+use crate::ui_layout::{point_in_rect, shed_bag_slot_rect, shed_slot_rect};
+
+pub const SHED_SLOTS: usize = 24;
+
+impl GameState {
+    pub(crate) fn toggle_shed_screen(&mut self) {
+        self.shed_open = !self.shed_open;
+    }
+
+    pub(crate) fn update_shed_screen(&mut self) {
+        if is_key_pressed(KeyCode::S) || is_key_pressed(KeyCode::Escape) {
+            self.toggle_shed_screen();
+            return;
+        }
+        if is_key_pressed(KeyCode::O) {
+            self.perform_action(ReplayAction::ToggleShedOverflow);
+        }
+        if !is_mouse_button_pressed(MouseButton::Left) {
+            return;
+        }
+
+        let (mx, my) = mouse_position();
+        let bag_slots = self.inventory.slot_count();
+        let hit = |(x, y, w, h): (f32, f32, f32, f32)| point_in_rect(mx, my, x, y, w, h);
+        if let Some(slot) = (0..bag_slots).find(|index| hit(shed_bag_slot_rect(*index, bag_slots))) {
+            self.perform_action(ReplayAction::ShedStore { slot });
+        } else if let Some(slot) = (0..self.shed.slot_count()).find(|index| hit(shed_slot_rect(*index))) {
+            self.perform_action(ReplayAction::ShedTake { slot });
+        }
+    }
+
+    pub(crate) fn store_in_shed(&mut self, slot: usize) {
+        if self.phase == GamePhase::Garden && self.inventory.transfer_slot(slot, &mut self.shed) > 0 {
+            self.drop_undo();
+        }
+    }
+
+    pub(crate) fn take_from_shed(&mut self, slot: usize) {
+        if self.phase == GamePhase::Garden && self.shed.transfer_slot(slot, &mut self.inventory) > 0 {
+            self.drop_undo();
+        }
+    }
+
+    pub(crate) fn draw_shed_screen(&self) {
+        if self.shed_open {
+            render::draw_shed_screen(&self.inventory, &self.shed, self.settings.overflow_to_shed);
+        }
+    }
+}
@@ -23,6 +23,11 @@ pub const BAGIRA_PRICE_UNCOMMON_MIN: i32 = 500;
 pub const BAGIRA_PRICE_UNCOMMON_MAX: i32 = 900;
 pub const BAGIRA_PRICE_RARE_MIN: i32 = 1200;
 
+/// Leaves Bagira pays for an item worth `base_price`.
+pub fn buyback_payout(base_price: i32) -> i32 {
+    (base_price as f32 * BAGIRA_BUYBACK_RATE).floor() as i32
+}
+
 // --- Shared types ---
 
 #[derive(Clone, Debug)]
@@ -39,6 +44,7 @@ pub struct BuybackEntry {
 
 // --- Bagira ---
 
+#[derive(Clone)]
 pub struct BagiraStock {
     pub visible: Vec<ShopItem>,          // up to BAGIRA_STOCK_SIZE items on display
     pub buyback: VecDeque<BuybackEntry>, // FIFO reserve of consigned/discarded items
@@ -55,7 +61,7 @@ impl BagiraStock {
     /// Called when a player overflows or discards an item.
     /// Quotes a buyback entry into reserve and returns the leaves paid to player.
     pub fn consign(&mut self, item: ItemType, base_price: i32) -> i32 {
-        let paid = (base_price as f32 * BAGIRA_BUYBACK_RATE).floor() as i32;
+        let paid = buyback_payout(base_price);
         let buyback_price = (base_price as f32 * BAGIRA_RESELL_RATE).floor() as i32;
 
         if self.buyback.len() >= BAGIRA_BUYBACK_LIMIT {
@@ -75,6 +81,7 @@ impl BagiraStock {
 
 // --- Tarquin ---
 
+#[derive(Clone)]
 pub struct TarquinStock {
     pub free_food_remaining: usize, // resets to TARQUIN_FREE_FOOD_LIMIT each visit
     pub gamble_available: bool,
@@ -107,6 +114,7 @@ impl TarquinStock {
 
 // --- Shop ---
 
+#[derive(Clone)]
 pub struct Shop {
     pub bagira: BagiraStock,
     pub tarquin: TarquinStock,
@@ -0,0 +1,92 @@
+// --- Spawn weights ---
+//
+// Every new tile is drawn from the board's spawn table. The table starts from
+// the biome's own weights, or the level file's `weights` line when it has
+// one, and board items such as the Tile Charm scale single kinds on top. The
+// table is set again whenever a level starts, and only the modifiers from
+// board items still in effect are put back.
+
+use crate::rng::RngStream;
+use crate::tile::{Tile, TileType};
+
+/// Relative spawn chance per kind, in `SPAWN_KINDS` order:
+/// Sun, Moon, Water, Leaf, Exotic.
+pub type SpawnWeights = [u32; 5];
+
+pub const SPAWN_KINDS: [TileType; 5] = [TileType::Sun, TileType::Moon, TileType::Water, TileType::Leaf, TileType::Exotic];
+
+pub const UNIFORM_SPAWN_WEIGHTS: SpawnWeights = [1; 5];
+
+/// Default weights by biome index. Biomes past the end use the last row.
+pub const BIOME_SPAWN_WEIGHTS: [SpawnWeights; 8] = [
+    [1, 1, 1, 1, 1], // 0 Forest Floor: even, as it always was
+    [2, 3, 3, 2, 2], // 1 Deep Cave: little sun, moon and water pool
+    [3, 2, 1, 2, 2], // 2 Volcanic Rift: scorched, water is scarce
+    [2, 3, 2, 1, 2], // 3 Frozen Tundra: long nights, few leaves
+    [1, 2, 4, 2, 2], // 4 Ocean Trench: water everywhere
+    [3, 3, 2, 2, 1], // 5 Sky Realm: sun and moon both up
+    [2, 2, 2, 2, 3], // 6 Fungal Wastes: Exotics bloom
+    [2, 4, 2, 1, 3], // 7 Celestial Void: moonlit, strange
+];
+
+pub fn biome_spawn_weights(biome: usize) -> SpawnWeights {
+    BIOME_SPAWN_WEIGHTS[biome.min(BIOME_SPAWN_WEIGHTS.len() - 1)]
+}
+
+/// Where `kind` sits in a `SpawnWeights` array, if it can spawn at all.
+pub fn spawn_index(kind: TileType) -> Option<usize> {
+    SPAWN_KINDS.iter().position(|candidate| *candidate == kind)
+}
+
+/// A runtime change to one kind's weight, in percent: 200 makes it twice as
+/// likely, 0 stops it spawning.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SpawnModifier {
+    pub kind: TileType,
+    pub percent: u32,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct SpawnTable {
+    base: SpawnWeights,
+    modifiers: Vec<SpawnModifier>,
+}
+
+impl Default for SpawnTable {
+    fn default() -> Self {
+        Self::new(UNIFORM_SPAWN_WEIGHTS)
+    }
+}
+
+impl SpawnTable {
+    pub fn new(base: SpawnWeights) -> Self {
+        Self { base, modifiers: vec![] }
+    }
+
+    pub fn add_modifier(&mut self, kind: TileType, percent: u32) {
+        self.modifiers.push(SpawnModifier { kind, percent });
+    }
+
+    /// Base weights with the modifiers applied. Without modifiers this is the
+    /// base as-is, so the draws match an unmodified table exactly.
+    pub fn weights(&self) -> SpawnWeights {
+        if self.modifiers.is_empty() {
+            return self.base;
+        }
+        let mut weights = self.base.map(|weight| weight * 100);
+        for modifier in &self.modifiers {
+            if let Some(index) = spawn_index(modifier.kind) {
+                weights[index] = weights[index] * modifier.percent / 100;
+            }
+        }
+        // Modifiers can't empty the table; fall back to the base if they try.
+        if weights.iter().all(|weight| *weight == 0) {
+            return self.base;
+        }
+        weights
+    }
+
+    pub fn roll(&self, rng: &mut RngStream) -> Tile {
+        Tile::new_random(rng, &self.weights())
+    }
+}
@@ -0,0 +1,145 @@
+// --- Targeted board items ---
+//
+// Some of Bagira's items act on a spot the player picks. Clicking one in the
+// HUD arms it; the next pick on the board aims it, and Escape puts it away
+// unused. Picks arrive as the same `Select` actions as a swap, so the cursor
+// keys aim items too and replays only need the arming on top.
+
+use crate::economy;
+use crate::game_state::GameState;
+use crate::inventory::{BoardModifierKind, ItemType};
+use crate::replay::ReplayAction;
+use crate::scoring::ScoreBreakdown;
+use crate::ui_layout::Layout;
+
+pub const TARGETED_KINDS: [BoardModifierKind; 3] = [
+    BoardModifierKind::TileRemove,
+    BoardModifierKind::FreeSwap,
+    BoardModifierKind::RowClear,
+];
+
+pub fn is_targeted(kind: BoardModifierKind) -> bool {
+    TARGETED_KINDS.contains(&kind)
+}
+
+/// An item waiting for the player to pick where it goes.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ArmedItem {
+    pub kind: BoardModifierKind,
+    pub first: Option<(usize, usize)>, // Swap Twine's first pick
+}
+
+/// What to tell the player while an item is armed.
+pub fn armed_prompt(armed: ArmedItem) -> &'static str {
+    match (armed.kind, armed.first) {
+        (BoardModifierKind::TileRemove, _) => "Trowel: pick a tile to dig out",
+        (BoardModifierKind::FreeSwap, None) => "Swap Twine: pick the first tile",
+        (BoardModifierKind::FreeSwap, Some(_)) => "Swap Twine: pick any tile to swap with",
+        (BoardModifierKind::RowClear, _) => "Scythe: pick a row to clear",
+        _ => "",
+    }
+}
+
+impl GameState {
+    /// A click on an inventory chip: targeted items arm (or put away if
+    /// already armed), other board items and food are used straight away.
+    pub(crate) fn click_inventory_slot(&mut self, index: usize) {
+        let Some(item) = self.inventory.slots.get(index).and_then(|slot| slot.item) else {
+            return;
+        };
+        match item {
+            ItemType::BoardModifier(kind) if self.armed.is_some_and(|armed| armed.kind == kind) => {
+                self.perform_action(ReplayAction::Disarm);
+            }
+            ItemType::BoardModifier(kind) if is_targeted(kind) => self.perform_action(ReplayAction::ArmItem(kind)),
+            ItemType::BoardModifier(kind) => self.perform_action(ReplayAction::UseModifier(kind)),
+            ItemType::FoodBuff(kind) => self.perform_action(ReplayAction::EatFood(kind)),
+            _ => {}
+        }
+    }
+
+    pub(crate) fn arm_item(&mut self, kind: BoardModifierKind) {
+        let carried = economy::inventory_count(&self.inventory, ItemType::BoardModifier(kind)) > 0;
+        if is_targeted(kind) && carried && self.board_idle() {
+            self.selected = None;
+            self.armed = Some(ArmedItem { kind, first: None });
+        }
+    }
+
+    pub(crate) fn disarm_item(&mut self) {
+        self.armed = None;
+    }
+
+    /// Items only hit gems; holes and ingredients are left alone.
+    fn can_target(&self, x: usize, y: usize) -> bool {
+        x < self.grid.width() && y < self.grid.height() && self.grid.is_playable(x, y) && self.grid[x][y].kind.is_gem()
+    }
+
+    /// The cells the armed item would hit if aimed at (x, y).
+    fn target_cells(&self, armed: ArmedItem, (x, y): (usize, usize)) -> Vec<(usize, usize)> {
+        match armed.kind {
+            BoardModifierKind::TileRemove if self.can_target(x, y) => vec![(x, y)],
+            BoardModifierKind::RowClear => (0..self.grid.width()).filter(|cx| self.can_target(*cx, y)).map(|cx| (cx, y)).collect(),
+            BoardModifierKind::FreeSwap => {
+                // Brambles still hold their tile, even against the twine.
+                let pick = (self.can_target(x, y) && !self.grid[x][y].blocker).then_some((x, y));
+                armed.first.into_iter().chain(pick).collect()
+            }
+            _ => vec![],
+        }
+    }
+
+    /// Cells to highlight under the pointer or cursor while an item is armed.
+    pub(crate) fn targeting_preview(&self, layout: &Layout) -> Vec<(usize, usize)> {
+        let Some(armed) = self.armed else {
+            return vec![];
+        };
+        match self.cursor.or_else(|| self.hovered_cell(layout)) {
+            Some(cell) => self.target_cells(armed, cell),
+            None => armed.first.into_iter().collect(),
+        }
+    }
+
+    /// A board pick while an item is armed. The item is spent once it acts.
+    pub(crate) fn aim_armed_item(&mut self, x: usize, y: usize) {
+        let Some(armed) = self.armed else {
+            return;
+        };
+        if !self.board_idle() {
+            return;
+        }
+
+        if armed.kind == BoardModifierKind::FreeSwap {
+            if self.target_cells(ArmedItem { first: None, ..armed }, (x, y)).is_empty() {
+                return;
+            }
+            match armed.first {
+                None => self.armed = Some(ArmedItem { first: Some((x, y)), ..armed }),
+                Some(first) if first == (x, y) => self.armed = Some(ArmedItem { first: None, ..armed }),
+                Some(first) => {
+                    self.spend_armed_item(armed.kind);
+                    self.start_free_swap(first, (x, y));
+                }
+            }
+            return;
+        }
+
+        let cells = self.target_cells(armed, (x, y));
+        if cells.is_empty() {
+            return;
+        }
+        self.spend_armed_item(armed.kind);
+        self.chain = 0;
+        self.move_score = ScoreBreakdown::default();
+        self.last_swap = None;
+        let triggered = cells.into_iter().map(|(cx, cy)| (cx, cy, self.grid[cx][cy].kind)).collect();
+        self.begin_match_clear(vec![], triggered, false);
+    }
+
+    fn spend_armed_item(&mut self, kind: BoardModifierKind) {
+        self.inventory.take_one(ItemType::BoardModifier(kind));
+        self.armed = None;
+        self.drop_undo();
+        self.reset_idle_hint();
+    }
+}
@@ -1,5 +1,7 @@
 use macroquad::prelude::*;
-use rand::gen_range;
+
+use crate::spawn::{SpawnWeights, SPAWN_KINDS};
+use crate::rng::RngStream;
 
 // We use 'pub' to make these visible to other files
 #[derive(Clone, Copy, PartialEq, Debug)]
@@ -8,45 +10,105 @@ pub enum TileType {
     Moon,
     Water,
     Leaf,
-    Exotic, // The shape-shifter
+    Exotic, // The shape-shifter: wildcard in any run
+    Ingredient, // never matches; delivered by reaching the bottom row
     Empty,
 }
 
+impl TileType {
+    pub const COLORS: [TileType; 4] = [TileType::Sun, TileType::Moon, TileType::Water, TileType::Leaf];
+
+    /// Gems can be blasted, converted and colour-cleared. Ingredients and
+    /// empty cells can't.
+    pub fn is_gem(self) -> bool {
+        !matches!(self, TileType::Ingredient | TileType::Empty)
+    }
+}
+
+/// What a cleared Exotic does, by biome. Mirrors `Tile::get_color`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ExoticTwist {
+    Rose,     // Garden: blooms for bonus leaves
+    Mushroom, // Thicket: spores clear its four neighbours
+    Eye,      // Void: its gaze turns its neighbours into the colour it matched as
+}
+
+impl ExoticTwist {
+    pub fn for_biome(biome: usize) -> Self {
+        match biome {
+            0 => ExoticTwist::Rose,
+            1 => ExoticTwist::Mushroom,
+            _ => ExoticTwist::Eye,
+        }
+    }
+}
+
+/// Power-up carried by a tile, created from 4+ matches and fired when cleared.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SpecialGem {
+    LineHorizontal, // 4 in a row: clears its whole row
+    LineVertical,   // 4 in a column: clears its whole column
+    Bomb,           // L/T shape: clears the 3x3 around it
+    ColorClear,     // 5 in a line: clears every tile of one kind
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct Tile {
     pub kind: TileType,
+    pub offset_x: f32, // draw offsets in pixels while the tile slides into its cell
     pub offset_y: f32,
+    pub fall_velocity: f32, // pixels per second, down is positive
+    pub fall_delay: f32,    // seconds to hang before falling, for the column stagger
+    pub special: Option<SpecialGem>,
+    pub blocker: bool, // bramble: can't be swapped, breaks when the tile clears
 }
 
 impl Tile {
-    pub fn new_random() -> Self {
-        let kind = match gen_range(0, 5) {
-            0 => TileType::Sun,
-            1 => TileType::Moon,
-            2 => TileType::Water,
-            3 => TileType::Leaf,
-            _ => TileType::Exotic,
-        };
-        Tile { kind, offset_y: 0.0 }
+    pub const EMPTY: Self = Self {
+        kind: TileType::Empty,
+        offset_x: 0.0,
+        offset_y: 0.0,
+        fall_velocity: 0.0,
+        fall_delay: 0.0,
+        special: None,
+        blocker: false,
+    };
+
+    /// Pick a kind with chances proportional to `weights`. Uniform weights
+    /// draw exactly like the old fixed 1-in-5 roll.
+    pub fn new_random(rng: &mut RngStream, weights: &SpawnWeights) -> Self {
+        let total: u32 = weights.iter().sum();
+        let mut roll = rng.gen_range(0, total.max(1) as usize) as u32;
+        let mut pick = weights.len() - 1;
+        for (index, weight) in weights.iter().enumerate() {
+            if roll < *weight {
+                pick = index;
+                break;
+            }
+            roll -= weight;
+        }
+        Tile { kind: SPAWN_KINDS[pick], ..Tile::EMPTY }
     }
 
-    pub fn get_color(&self, level: i32) -> Color {
+    pub fn ingredient() -> Self {
+        Tile { kind: TileType::Ingredient, ..Tile::EMPTY }
+    }
+
+    pub fn get_color(&self, biome: usize) -> Color {
         match self.kind {
             TileType::Sun => GOLD,
             TileType::Moon => SKYBLUE,
             TileType::Water => BLUE,
             TileType::Leaf => BLANK, // Drawn via texture; color unused
+            TileType::Ingredient => BROWN,
             TileType::Empty => BLANK,
             
             // EXOTIC LOGIC: Changes based on the Level Set
-            TileType::Exotic => {
-                let set_index = (level - 1) / 3; // Change every 3 levels
-                match set_index {
-                    0 => PINK,      // Garden (Rose)
-                    1 => PURPLE,    // Thicket (Mushroom)
-                    _ => RED,       // Void (Eye)
-                }
-            }
+            TileType::Exotic => match ExoticTwist::for_biome(biome) {
+                ExoticTwist::Rose => PINK,       // Garden
+                ExoticTwist::Mushroom => PURPLE, // Thicket
+                ExoticTwist::Eye => RED,         // Void
+            },
         }
     }
 }
\ No newline at end of file
@@ -1,5 +1,7 @@
 use macroquad::prelude::*;
 
+use crate::inventory::INVENTORY_SLOTS;
+
 pub struct Layout {
     pub tile_size: f32,
     pub grid_offset_x: f32,
@@ -58,6 +60,26 @@ pub fn playing_visit_garden_button_rect(layout: &Layout) -> (f32, f32, f32, f32)
     (btn_x, btn_y, btn_w, btn_h)
 }
 
+/// One of the HUD inventory chips, in a single row under the wallet. The
+/// chips shrink to fit as the satchel grows.
+pub fn playing_inventory_slot_rect(layout: &Layout, index: usize, slot_count: usize) -> (f32, f32, f32, f32) {
+    let row_h = layout.tile_size * 0.5;
+    let inv_title_y = layout.grid_offset_y + row_h * 4.25;
+    let slot_count = slot_count.max(1);
+    let gap = layout.ui_panel_width * 0.018 * (INVENTORY_SLOTS as f32 / slot_count as f32).min(1.0);
+    let fit = (layout.ui_panel_width * 0.92 - gap * (slot_count - 1) as f32) / slot_count as f32;
+    let size = fit.min(row_h * 0.98);
+    let x = layout.ui_panel_x + index as f32 * (size + gap);
+    (x, inv_title_y + row_h * 0.28, size, size)
+}
+
+/// Small square beside the garden button.
+pub fn playing_undo_button_rect(layout: &Layout) -> (f32, f32, f32, f32) {
+    let (visit_x, visit_y, visit_w, visit_h) = playing_visit_garden_button_rect(layout);
+    let gap = layout.ui_panel_width * 0.02;
+    (visit_x + visit_w + gap, visit_y, visit_h, visit_h)
+}
+
 pub fn playing_descend_button_rect(layout: &Layout) -> (f32, f32, f32, f32) {
     let row_h = layout.tile_size * 0.5;
     let (btn_x, visit_y, btn_w, btn_h) = playing_visit_garden_button_rect(layout);
@@ -65,6 +87,27 @@ pub fn playing_descend_button_rect(layout: &Layout) -> (f32, f32, f32, f32) {
     (btn_x, btn_y, btn_w, btn_h)
 }
 
+pub const INVENTORY_SCREEN_MIN_COLUMNS: usize = 4;
+
+/// A slot on the inventory screen. Slots sit in two rows, or rows of
+/// INVENTORY_SCREEN_MIN_COLUMNS for a small satchel, and shrink to fit.
+pub fn inventory_screen_slot_rect(index: usize, slot_count: usize) -> (f32, f32, f32, f32) {
+    let sw = screen_width();
+    let sh = screen_height();
+    let columns = slot_count.div_ceil(2).max(INVENTORY_SCREEN_MIN_COLUMNS);
+    let size = (sh * 0.14).min(sw * 0.85 / (columns as f32 * 1.18));
+    let gap = size * 0.18;
+    let row_w = columns as f32 * size + (columns - 1) as f32 * gap;
+    let (column, row) = (index % columns, index / columns);
+    ((sw - row_w) * 0.5 + column as f32 * (size + gap), sh * 0.3 + row as f32 * (size + gap), size, size)
+}
+
+pub fn inventory_sort_button_rect() -> (f32, f32, f32, f32) {
+    let sw = screen_width();
+    let sh = screen_height();
+    (sw * 0.42, sh * 0.7, sw * 0.16, sh * 0.07)
+}
+
 pub fn garden_return_button_rect() -> (f32, f32, f32, f32) {
     let sw = screen_width();
     let sh = screen_height();
@@ -77,6 +120,36 @@ pub fn garden_hunt_button_rect() -> (f32, f32, f32, f32) {
     (sw * 0.82, sh * 0.02, sw * 0.15, sh * 0.06)
 }
 
+pub fn garden_shed_button_rect() -> (f32, f32, f32, f32) {
+    let sw = screen_width();
+    let sh = screen_height();
+    (sw * 0.82, sh * 0.14, sw * 0.15, sh * 0.06)
+}
+
+pub const SHED_COLUMNS: usize = 8;
+
+/// A satchel slot on the shed screen, in one row along the top.
+pub fn shed_bag_slot_rect(index: usize, slot_count: usize) -> (f32, f32, f32, f32) {
+    let sw = screen_width();
+    let sh = screen_height();
+    let slot_count = slot_count.max(1);
+    let size = (sh * 0.1).min(sw * 0.85 / (slot_count as f32 * 1.18));
+    let gap = size * 0.18;
+    let row_w = slot_count as f32 * size + (slot_count - 1) as f32 * gap;
+    ((sw - row_w) * 0.5 + index as f32 * (size + gap), sh * 0.22, size, size)
+}
+
+/// A shed slot, in rows of SHED_COLUMNS under the satchel.
+pub fn shed_slot_rect(index: usize) -> (f32, f32, f32, f32) {
+    let sw = screen_width();
+    let sh = screen_height();
+    let size = (sh * 0.1).min(sw * 0.85 / (SHED_COLUMNS as f32 * 1.18));
+    let gap = size * 0.18;
+    let row_w = SHED_COLUMNS as f32 * size + (SHED_COLUMNS - 1) as f32 * gap;
+    let (column, row) = (index % SHED_COLUMNS, index / SHED_COLUMNS);
+    ((sw - row_w) * 0.5 + column as f32 * (size + gap), sh * 0.44 + row as f32 * (size + gap), size, size)
+}
+
 pub fn hunt_return_button_rect() -> (f32, f32, f32, f32) {
     let sw = screen_width();
     let sh = screen_height();
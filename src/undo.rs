@@ -0,0 +1,123 @@
+// --- Undo last move ---
+//
+// Before a swap is judged, everything it can change is copied aside: the
+// board, points, inventory and the shed overflow may land in, active
+// modifiers and buffs, illicit cost, the shop Bagira consigns into and the RNG
+// streams. Restoring the RNG too means the tiles that refill the board
+// afterwards are the same ones that would have fallen before.
+//
+// Only the last move can be undone, and only while it's still on the board:
+// a move that set off a cascade, a new level, a trip to the garden, or any
+// item, food or satchel action after it drops the snapshot. Each undo in a
+// level costs more leaves than the last.
+
+use crate::board::Board;
+use crate::buffs::ActiveBuff;
+use crate::game_state::GameState;
+use crate::inventory::Inventory;
+use crate::modifiers::ActiveModifier;
+use crate::objectives::ObjectiveTracker;
+use crate::rng::GameRng;
+use crate::scoring::ScoreBreakdown;
+use crate::shop::Shop;
+
+pub const UNDO_FEE_START: i32 = 50;
+pub const UNDO_FEE_STEP: i32 = 50; // added for each undo already used this level
+
+/// The game as it stood before the last move.
+#[derive(Clone)]
+pub struct UndoSnapshot {
+    grid: Board,
+    total_points: i32,
+    spent_points: i32,
+    illegal_move_cost: i32,
+    inventory: Inventory,
+    shed: Inventory,
+    modifiers: Vec<ActiveModifier>,
+    buffs: Vec<ActiveBuff>,
+    shop: Shop,
+    rng: GameRng,
+    moves_made: u32,
+    objectives: Vec<ObjectiveTracker>,
+    move_score: ScoreBreakdown,
+}
+
+impl GameState {
+    /// Remember the board as it was before the swap of `a` and `b`. The grid
+    /// already holds the swapped tiles, so they're put back in the copy.
+    pub(crate) fn snapshot_for_undo(&mut self, a: (usize, usize), b: (usize, usize)) {
+        let mut grid = self.grid.clone();
+        let temp = grid[a.0][a.1];
+        grid[a.0][a.1] = grid[b.0][b.1];
+        grid[b.0][b.1] = temp;
+        for column in grid.columns_mut() {
+            for tile in column.iter_mut() {
+                tile.offset_x = 0.0;
+                tile.offset_y = 0.0;
+                tile.fall_velocity = 0.0;
+                tile.fall_delay = 0.0;
+            }
+        }
+        self.undo = Some(UndoSnapshot {
+            grid,
+            total_points: self.total_points,
+            spent_points: self.spent_points,
+            illegal_move_cost: self.illegal_move_cost,
+            inventory: self.inventory.clone(),
+            shed: self.shed.clone(),
+            modifiers: self.modifiers.clone(),
+            buffs: self.buffs.clone(),
+            shop: self.shop.clone(),
+            rng: self.rng,
+            moves_made: self.moves_made,
+            objectives: self.objectives.clone(),
+            move_score: self.move_score.clone(),
+        });
+    }
+
+    /// Something the snapshot can't take back has happened since the move,
+    /// so undoing now would roll back more than the move itself.
+    pub(crate) fn drop_undo(&mut self) {
+        self.undo = None;
+    }
+
+    pub(crate) fn undo_fee(&self) -> i32 {
+        UNDO_FEE_START + UNDO_FEE_STEP * self.undos_used as i32
+    }
+
+    /// The fee if the last move can be undone right now and the player can
+    /// pay it from what they'd have after the undo.
+    pub(crate) fn undo_available(&self) -> Option<i32> {
+        let snapshot = self.undo.as_ref()?;
+        let fee = self.undo_fee();
+        (self.board_idle() && snapshot.total_points - snapshot.spent_points >= fee).then_some(fee)
+    }
+
+    pub(crate) fn undo_last_move(&mut self) {
+        let Some(fee) = self.undo_available() else {
+            return;
+        };
+        let Some(snapshot) = self.undo.take() else {
+            return;
+        };
+        self.grid = snapshot.grid;
+        self.total_points = snapshot.total_points;
+        self.spent_points = snapshot.spent_points + fee;
+        self.illegal_move_cost = snapshot.illegal_move_cost;
+        self.inventory = snapshot.inventory;
+        self.shed = snapshot.shed;
+        self.modifiers = snapshot.modifiers;
+        self.buffs = snapshot.buffs;
+        self.shop = snapshot.shop;
+        self.rng = snapshot.rng;
+        self.moves_made = snapshot.moves_made;
+        self.objectives = snapshot.objectives;
+        self.move_score = snapshot.move_score;
+        self.undos_used += 1;
+        self.last_swap = None;
+        self.selected = None;
+        self.armed = None;
+        self.popups.clear();
+        self.reset_idle_hint();
+    }
+}
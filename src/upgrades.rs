@@ -0,0 +1,103 @@
+// --- Bagira's satchel upgrades ---
+//
+// Bought at the shrine between biomes. A Bigger Satchel adds slots and Deeper
+// Pockets let each slot hold a taller stack; every tier costs more than the
+// last. The raised capacity lives on the inventory itself. Saves keep the
+// slot count and stack size, though not what's in the satchel.
+
+use macroquad::prelude::*;
+
+use crate::game_state::{GamePhase, GameState};
+use crate::inventory::{INVENTORY_SLOTS, STACK_MAX};
+use crate::replay::ReplayAction;
+
+pub const SATCHEL_SLOTS_STEP: usize = 2;
+pub const SATCHEL_SLOTS_MAX: usize = 16;
+pub const SATCHEL_PRICE_START: i32 = 1500;
+pub const SATCHEL_PRICE_STEP: i32 = 1000; // added for each tier already bought
+
+pub const POCKETS_STACK_STEP: u32 = 5;
+pub const POCKETS_STACK_MAX: u32 = 30;
+pub const POCKETS_PRICE_START: i32 = 1200;
+pub const POCKETS_PRICE_STEP: i32 = 800;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BagiraUpgrade {
+    BiggerSatchel, // more slots
+    DeeperPockets, // taller stacks
+}
+
+impl GameState {
+    /// B buys a Bigger Satchel, P Deeper Pockets.
+    pub(crate) fn handle_upgrade_keys(&mut self) {
+        if is_key_pressed(KeyCode::B) {
+            self.perform_action(ReplayAction::BuyUpgrade(BagiraUpgrade::BiggerSatchel));
+        }
+        if is_key_pressed(KeyCode::P) {
+            self.perform_action(ReplayAction::BuyUpgrade(BagiraUpgrade::DeeperPockets));
+        }
+    }
+
+    /// The next tier's price, or None once the upgrade is maxed out.
+    pub(crate) fn upgrade_price(&self, upgrade: BagiraUpgrade) -> Option<i32> {
+        match upgrade {
+            BagiraUpgrade::BiggerSatchel => {
+                let slots = self.inventory.slot_count();
+                let tier = (slots.saturating_sub(INVENTORY_SLOTS) / SATCHEL_SLOTS_STEP) as i32;
+                (slots < SATCHEL_SLOTS_MAX).then_some(SATCHEL_PRICE_START + SATCHEL_PRICE_STEP * tier)
+            }
+            BagiraUpgrade::DeeperPockets => {
+                let stack_max = self.inventory.stack_max();
+                let tier = (stack_max.saturating_sub(STACK_MAX) / POCKETS_STACK_STEP) as i32;
+                (stack_max < POCKETS_STACK_MAX).then_some(POCKETS_PRICE_START + POCKETS_PRICE_STEP * tier)
+            }
+        }
+    }
+
+    /// Pay for the next tier out of the wallet. Only Bagira's stall at the
+    /// shrine sells these.
+    pub(crate) fn buy_upgrade(&mut self, upgrade: BagiraUpgrade) {
+        if self.phase != GamePhase::Shop {
+            return;
+        }
+        let Some(price) = self.upgrade_price(upgrade) else {
+            return;
+        };
+        if self.get_leaves_wallet() < price {
+            return;
+        }
+        self.spent_points += price;
+        self.drop_undo();
+        let (slots, stack_max) = (self.inventory.slot_count(), self.inventory.stack_max());
+        match upgrade {
+            BagiraUpgrade::BiggerSatchel => {
+                self.inventory.raise_capacity((slots + SATCHEL_SLOTS_STEP).min(SATCHEL_SLOTS_MAX), stack_max)
+            }
+            BagiraUpgrade::DeeperPockets => {
+                self.inventory.raise_capacity(slots, (stack_max + POCKETS_STACK_STEP).min(POCKETS_STACK_MAX))
+            }
+        }
+    }
+
+    /// What Bagira offers at the shrine.
+    pub(crate) fn upgrade_lines(&self) -> Vec<String> {
+        let offer = |key: &str, name: &str, now: String, price: Option<i32>| match price {
+            Some(price) => format!("[{}] {} ({}) - {} Leaves", key, name, now, price),
+            None => format!("{} ({}) - sold out", name, now),
+        };
+        vec![
+            offer(
+                "B",
+                "Bigger Satchel",
+                format!("{} slots", self.inventory.slot_count()),
+                self.upgrade_price(BagiraUpgrade::BiggerSatchel),
+            ),
+            offer(
+                "P",
+                "Deeper Pockets",
+                format!("stacks of {}", self.inventory.stack_max()),
+                self.upgrade_price(BagiraUpgrade::DeeperPockets),
+            ),
+        ]
+    }
+}